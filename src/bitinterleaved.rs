@@ -0,0 +1,154 @@
+//! Xoodoo64 on 32-bit words, for targets without 64-bit rotations.
+//!
+//! On 32-bit CPUs a 64-bit rotation takes several instructions. As in Keccak's
+//! 32-bit implementations, each lane is split into the word of its even bits
+//! and the word of its odd bits. Rotating the lane left by `2s` rotates both
+//! words by `s`. Rotating it by `2s + 1` rotates the odd word by `s + 1` and the
+//! even word by `s`, and the two words swap places. The state is split once on
+//! load and joined once on store, and the round constants are split at compile
+//! time.
+
+use crate::constants;
+use crate::trace::{Step, Tracer};
+
+/// A lane as its even bits and its odd bits.
+pub type Lane = [u32; 2];
+
+/// 3 planes of 2 lanes, as in `steps::xoodoo64`.
+pub type State = [Lane; 6];
+
+/// `constants::WIDENED`, split.
+pub const WIDENED: [Lane; 12] = split_all(constants::WIDENED);
+
+/// `constants::LFSR`, split.
+pub const LFSR: [Lane; 12] = split_all(constants::LFSR);
+
+/// Exchanges the bits of `x` selected by `mask` with the bits `shift` places
+/// to their left.
+const fn delta_swap(x: u64, mask: u64, shift: u32) -> u64 {
+    let t = (x ^ (x >> shift)) & mask;
+    x ^ t ^ (t << shift)
+}
+
+/// Moves the even bits of `x` to the low half and the odd bits to the high
+/// half, in order. Its inverse applies the same swaps in reverse order.
+const SWAPS: [(u64, u32); 5] = [
+    (0x2222_2222_2222_2222, 1),
+    (0x0c0c_0c0c_0c0c_0c0c, 2),
+    (0x00f0_00f0_00f0_00f0, 4),
+    (0x0000_ff00_0000_ff00, 8),
+    (0x0000_0000_ffff_0000, 16),
+];
+
+pub const fn split(mut x: u64) -> Lane {
+    let mut i = 0;
+    while i < SWAPS.len() {
+        x = delta_swap(x, SWAPS[i].0, SWAPS[i].1);
+        i += 1;
+    }
+    [x as u32, (x >> 32) as u32]
+}
+
+pub const fn join([even, odd]: Lane) -> u64 {
+    let mut x = (odd as u64) << 32 | even as u64;
+    let mut i = SWAPS.len();
+    while i > 0 {
+        i -= 1;
+        x = delta_swap(x, SWAPS[i].0, SWAPS[i].1);
+    }
+    x
+}
+
+const fn split_all(constants: [u64; 12]) -> [Lane; 12] {
+    let mut out = [[0; 2]; 12];
+    let mut i = 0;
+    while i < 12 {
+        out[i] = split(constants[i]);
+        i += 1;
+    }
+    out
+}
+
+/// The 64-bit rotation of the joined lane, as two 32-bit rotations.
+#[inline(always)]
+fn rotate_left([even, odd]: Lane, r: u32) -> Lane {
+    if r.is_multiple_of(2) {
+        [even.rotate_left(r / 2), odd.rotate_left(r / 2)]
+    } else {
+        [odd.rotate_left(r / 2 + 1), even.rotate_left(r / 2)]
+    }
+}
+
+#[inline(always)]
+fn xor(a: Lane, b: Lane) -> Lane {
+    [a[0] ^ b[0], a[1] ^ b[1]]
+}
+
+/// `a ^ (!b & c)`.
+#[inline(always)]
+fn xor_andnot(a: Lane, b: Lane, c: Lane) -> Lane {
+    [a[0] ^ (!b[0] & c[0]), a[1] ^ (!b[1] & c[1])]
+}
+
+#[inline(always)]
+pub fn load(bytes: &[u8; 48]) -> State {
+    std::array::from_fn(|i| split(crate::read64(&bytes[8 * i..8 * i + 8])))
+}
+
+#[inline(always)]
+pub fn store(a: &State, bytes: &mut [u8; 48]) {
+    for (lane, chunk) in a.iter().zip(bytes.chunks_exact_mut(8)) {
+        crate::write64(join(*lane), chunk);
+    }
+}
+
+/// The joined lanes of `a`, as a `Tracer` takes them.
+#[inline(always)]
+fn lanes(a: &State) -> [[u64; 1]; 6] {
+    a.map(|lane| [join(lane)])
+}
+
+/// One round per key; the same rounds as `steps::xoodoo64::permute`, recording
+/// the state after every step.
+#[inline(always)]
+pub fn permute(a: &mut State, round_keys: &[Lane], tracer: &mut impl Tracer) {
+    for (round, &round_key) in round_keys.iter().enumerate() {
+        // theta
+        let p: [Lane; 2] = std::array::from_fn(|x| xor(xor(a[x], a[x + 2]), a[x + 4]));
+        for x in 0..2 {
+            let p = p[(x + 1) % 2];
+            let e = xor(rotate_left(p, 10), rotate_left(p, 29));
+            a[x] = xor(a[x], e);
+            a[x + 2] = xor(a[x + 2], e);
+            a[x + 4] = xor(a[x + 4], e);
+        }
+        tracer.record(round, Step::Theta, &lanes(a));
+
+        // rho west
+        a.swap(2, 3);
+        a[4] = rotate_left(a[4], 23);
+        a[5] = rotate_left(a[5], 23);
+        tracer.record(round, Step::RhoWest, &lanes(a));
+
+        // iota
+        a[0] = xor(a[0], round_key);
+        tracer.record(round, Step::Iota, &lanes(a));
+
+        // chi
+        for x in 0..2 {
+            let (a0, a1, a2) = (a[x], a[x + 2], a[x + 4]);
+            a[x] = xor_andnot(a0, a1, a2);
+            a[x + 2] = xor_andnot(a1, a2, a0);
+            a[x + 4] = xor_andnot(a2, a0, a1);
+        }
+        tracer.record(round, Step::Chi, &lanes(a));
+
+        // rho east
+        a[2] = rotate_left(a[2], 1);
+        a[3] = rotate_left(a[3], 1);
+        a.swap(4, 5);
+        a[4] = rotate_left(a[4], 16);
+        a[5] = rotate_left(a[5], 16);
+        tracer.record(round, Step::RhoEast, &lanes(a));
+    }
+}
@@ -0,0 +1,289 @@
+//! Bitsliced Xoodoo and Xoodoo64, for evaluating many independent instances.
+//!
+//! A bitsliced state holds one word per bit of the 384-bit state, numbered as
+//! in `symbolic` (plane, then lane, then bit), and bit `k` of every word belongs
+//! to instance `k`. The lane shifts and rotations then move whole words, and
+//! theta, iota and chi are XORs, ANDs and NOTs of whole words, so one call
+//! permutes as many instances as the word has bits: 64 for `u64`, 128 for
+//! `u128` and 256 for `U64x4`, which compiles to AVX2 or NEON when the target
+//! has it.
+//!
+//! `pack` and `unpack` convert from and to the 48-byte layout of the scalar
+//! backends by transposing 64 by 64 bit blocks. Experiments that generate their
+//! inputs in bitsliced form, such as counters or cube variables, can skip them.
+//!
+//! `cargo run -r -- bitsliced` compares the time per instance with
+//! `xoodoo_scalar` and `xoodoo64_scalar`.
+
+use crate::variant::{Variant, XOODOO, XOODOO64};
+use std::hint::black_box;
+use std::ops::{BitAnd, BitXor, Not};
+use std::time::Instant;
+
+/// A word of bitsliced state: one bit per instance.
+pub trait Slice: Copy + BitXor<Output = Self> + BitAnd<Output = Self> + Not<Output = Self> {
+    const INSTANCES: usize;
+    const ZERO: Self;
+
+    /// Instances `64 * chunk` to `64 * chunk + 63`.
+    fn chunk(self, chunk: usize) -> u64;
+    fn set_chunk(&mut self, chunk: usize, bits: u64);
+}
+
+impl Slice for u64 {
+    const INSTANCES: usize = 64;
+    const ZERO: u64 = 0;
+
+    fn chunk(self, _: usize) -> u64 {
+        self
+    }
+
+    fn set_chunk(&mut self, _: usize, bits: u64) {
+        *self = bits;
+    }
+}
+
+impl Slice for u128 {
+    const INSTANCES: usize = 128;
+    const ZERO: u128 = 0;
+
+    fn chunk(self, chunk: usize) -> u64 {
+        (self >> (64 * chunk)) as u64
+    }
+
+    fn set_chunk(&mut self, chunk: usize, bits: u64) {
+        *self &= !((u64::MAX as u128) << (64 * chunk));
+        *self |= (bits as u128) << (64 * chunk);
+    }
+}
+
+/// Four `u64` slices operated on together, which the compiler vectorizes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct U64x4(pub [u64; 4]);
+
+impl BitXor for U64x4 {
+    type Output = U64x4;
+
+    #[inline(always)]
+    fn bitxor(self, other: U64x4) -> U64x4 {
+        U64x4(std::array::from_fn(|i| self.0[i] ^ other.0[i]))
+    }
+}
+
+impl BitAnd for U64x4 {
+    type Output = U64x4;
+
+    #[inline(always)]
+    fn bitand(self, other: U64x4) -> U64x4 {
+        U64x4(std::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+}
+
+impl Not for U64x4 {
+    type Output = U64x4;
+
+    #[inline(always)]
+    fn not(self) -> U64x4 {
+        U64x4(self.0.map(|w| !w))
+    }
+}
+
+impl Slice for U64x4 {
+    const INSTANCES: usize = 256;
+    const ZERO: U64x4 = U64x4([0; 4]);
+
+    fn chunk(self, chunk: usize) -> u64 {
+        self.0[chunk]
+    }
+
+    fn set_chunk(&mut self, chunk: usize, bits: u64) {
+        self.0[chunk] = bits;
+    }
+}
+
+pub type State<S> = [S; 384];
+
+/// Transposes a 64 by 64 bit matrix: bit `j` of `m[i]` becomes bit `i` of
+/// `m[j]`. Each pass swaps the off-diagonal blocks of the blocks of the
+/// previous one.
+fn transpose(m: &mut [u64; 64]) {
+    let mut width = 32;
+    let mut mask = 0x0000_0000_ffff_ffffu64;
+    while width != 0 {
+        for i in (0..64).filter(|i| i & width == 0) {
+            let t = ((m[i] >> width) ^ m[i + width]) & mask;
+            m[i] ^= t << width;
+            m[i + width] ^= t;
+        }
+        width >>= 1;
+        mask ^= mask << width;
+    }
+}
+
+/// Packs up to `S::INSTANCES` states; the remaining instances are zero.
+pub fn pack<S: Slice>(states: &[[u8; 48]]) -> State<S> {
+    assert!(states.len() <= S::INSTANCES);
+    let mut a = [S::ZERO; 384];
+    for (chunk, states) in states.chunks(64).enumerate() {
+        for (word, slices) in a.chunks_exact_mut(64).enumerate() {
+            let mut m = [0u64; 64];
+            for (row, state) in m.iter_mut().zip(states) {
+                *row = crate::read64(&state[8 * word..8 * word + 8]);
+            }
+            transpose(&mut m);
+            for (slice, bits) in slices.iter_mut().zip(m) {
+                slice.set_chunk(chunk, bits);
+            }
+        }
+    }
+    a
+}
+
+/// Unpacks the first `states.len()` instances.
+pub fn unpack<S: Slice>(a: &State<S>, states: &mut [[u8; 48]]) {
+    assert!(states.len() <= S::INSTANCES);
+    for (chunk, states) in states.chunks_mut(64).enumerate() {
+        for (word, slices) in a.chunks_exact(64).enumerate() {
+            let mut m: [u64; 64] = std::array::from_fn(|i| slices[i].chunk(chunk));
+            transpose(&mut m);
+            for (row, state) in m.into_iter().zip(states.iter_mut()) {
+                crate::write64(row, &mut state[8 * word..8 * word + 8]);
+            }
+        }
+    }
+}
+
+/// Bits per plane, in both permutations.
+const PLANE: usize = 128;
+
+/// Each lane of `src` rotated left by `r` bits, into `dst`.
+#[inline(always)]
+fn rotate_lanes<S: Slice>(dst: &mut [S], src: &[S], bits: usize, r: u32) {
+    let r = r as usize;
+    for (dst, src) in dst.chunks_exact_mut(bits).zip(src.chunks_exact(bits)) {
+        dst[r..].copy_from_slice(&src[..bits - r]);
+        dst[..r].copy_from_slice(&src[bits - r..]);
+    }
+}
+
+/// `src` shifted by `shift` lanes, into `dst`.
+#[inline(always)]
+fn shift_lanes<S: Slice>(dst: &mut [S], src: &[S], bits: usize, shift: usize) {
+    rotate_lanes(dst, src, PLANE, (shift * bits) as u32);
+}
+
+/// One round per constant, the steps as in `symbolic`. Lane shifts and
+/// rotations copy whole runs of words.
+#[inline(always)]
+pub fn permute<S: Slice>(v: &Variant, a: &mut State<S>, round_constants: &[u64]) {
+    let bits = v.lane_bits as usize;
+    let mut b = [S::ZERO; 384];
+    for &round_constant in round_constants {
+        // theta
+        let p: [S; PLANE] = std::array::from_fn(|i| a[i] ^ a[PLANE + i] ^ a[2 * PLANE + i]);
+        let mut shifted = [S::ZERO; PLANE];
+        shift_lanes(&mut shifted, &p, bits, v.theta_shift);
+        let (mut e0, mut e1) = ([S::ZERO; PLANE], [S::ZERO; PLANE]);
+        rotate_lanes(&mut e0, &shifted, bits, v.theta_rot[0]);
+        rotate_lanes(&mut e1, &shifted, bits, v.theta_rot[1]);
+        for (i, w) in b.iter_mut().enumerate() {
+            *w = a[i] ^ e0[i % PLANE] ^ e1[i % PLANE];
+        }
+
+        // rho west
+        let (b0, b1, b2) = (&b[..PLANE], &b[PLANE..2 * PLANE], &b[2 * PLANE..]);
+        a[..PLANE].copy_from_slice(b0);
+        shift_lanes(&mut a[PLANE..2 * PLANE], b1, bits, v.rho_west_shift);
+        rotate_lanes(&mut a[2 * PLANE..], b2, bits, v.rho_west_rot);
+
+        // iota
+        for (z, w) in a[..bits].iter_mut().enumerate() {
+            if round_constant >> z & 1 == 1 {
+                *w = !*w;
+            }
+        }
+
+        // chi
+        for i in 0..PLANE {
+            let (a0, a1, a2) = (a[i], a[PLANE + i], a[2 * PLANE + i]);
+            b[i] = a0 ^ (!a1 & a2);
+            b[PLANE + i] = a1 ^ (!a2 & a0);
+            b[2 * PLANE + i] = a2 ^ (!a0 & a1);
+        }
+
+        // rho east
+        let (b0, b1, b2) = (&b[..PLANE], &b[PLANE..2 * PLANE], &b[2 * PLANE..]);
+        a[..PLANE].copy_from_slice(b0);
+        rotate_lanes(&mut a[PLANE..2 * PLANE], b1, bits, v.rho_east_rot1);
+        shift_lanes(&mut shifted, b2, bits, v.rho_east_shift);
+        rotate_lanes(&mut a[2 * PLANE..], &shifted, bits, v.rho_east_rot2);
+    }
+}
+
+/// `S::INSTANCES` instances of `xoodoo_scalar`, with the given constants.
+#[inline(never)]
+pub fn xoodoo<S: Slice>(a: &mut State<S>, round_constants: &[u64]) {
+    permute(&XOODOO, a, round_constants);
+}
+
+/// `S::INSTANCES` instances of `xoodoo64_scalar`, with the given constants.
+#[inline(never)]
+pub fn xoodoo64<S: Slice>(a: &mut State<S>, round_constants: &[u64]) {
+    permute(&XOODOO64, a, round_constants);
+}
+
+/// Permutations per measurement.
+const PERMUTATIONS: usize = 1 << 22;
+
+/// Nanoseconds per instance of `f`, which permutes `instances` at a time.
+fn throughput(instances: usize, mut f: impl FnMut()) -> f64 {
+    let calls = PERMUTATIONS / instances;
+    let start = Instant::now();
+    for _ in 0..calls {
+        f();
+    }
+    start.elapsed().as_nanos() as f64 / (calls * instances) as f64
+}
+
+fn bench<S: Slice>(name: &str, f: fn(&mut State<S>, &[u64])) {
+    let constants = &crate::constants::WIDENED;
+    let mut a = black_box([S::ZERO; 384]);
+    let permute = throughput(S::INSTANCES, || f(black_box(&mut a), constants));
+    let mut states = black_box(vec![[0u8; 48]; S::INSTANCES]);
+    let with_packing = throughput(S::INSTANCES, || {
+        let mut a = pack::<S>(&states);
+        f(&mut a, constants);
+        unpack(&a, &mut states);
+    });
+    println!(
+        "{:<30} {:4}x {:>16.1} {:>16.1}",
+        name,
+        S::INSTANCES,
+        permute,
+        with_packing
+    );
+}
+
+pub fn main(args: &[String]) {
+    if !args.is_empty() {
+        crate::usage();
+    }
+    println!(
+        "{:<30} {:>5} {:>16} {:>16}",
+        "name", "par", "permute (ns)", "with pack (ns)"
+    );
+    for (name, f) in [
+        ("xoodoo_scalar", crate::xoodoo_scalar as fn(&mut [u8; 48])),
+        ("xoodoo64_scalar", crate::xoodoo64_scalar),
+    ] {
+        let mut x = black_box([0u8; 48]);
+        let ns = throughput(1, || f(black_box(&mut x)));
+        println!("{:<30} {:4}x {:>16.1} {:>16}", name, 1, ns, "-");
+    }
+    bench::<u64>("xoodoo_bitsliced_x64", xoodoo);
+    bench::<u128>("xoodoo_bitsliced_x128", xoodoo);
+    bench::<U64x4>("xoodoo_bitsliced_x256", xoodoo);
+    bench::<u64>("xoodoo64_bitsliced_x64", xoodoo64);
+    bench::<u128>("xoodoo64_bitsliced_x128", xoodoo64);
+    bench::<U64x4>("xoodoo64_bitsliced_x256", xoodoo64);
+}
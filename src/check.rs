@@ -0,0 +1,636 @@
+//! Self-checks of the backends against the models.
+//!
+//! The reduced-round scalar permutations are compared on random inputs with
+//! both the bit-level model in `symbolic` and the word-level model in
+//! `variant`, and every backend in `for_each_backend` with the bit-level model
+//! of the full permutation, and single-bit states through one round pin down
+//! the direction of theta's lane shift. Each function of `steps` is compared
+//! with the bit-level step, and composing them with the scalar permutations;
+//! the batch, interleaved-layout and fused functions are compared with
+//! permuting one state at a time, and the bit-interleaved and bitsliced
+//! permutations with the scalar ones. The `Linear` and `Difference`
+//! instantiations of the linear steps are checked against concrete evaluation.
+//! Failures are reported per check and make the process exit with status 1.
+
+use crate::constants::{LFSR, WIDENED};
+use crate::diffusion::PERMUTATIONS;
+use crate::symbolic::{self, Bit, Difference, Linear};
+use crate::variant::{Rng, VARIANTS, Variant, XOODOO, XOODOO64, random_state};
+use crate::{BackendVisitor, for_each_backend};
+use crate::{bitinterleaved, bitsliced, scalar, steps};
+
+const SAMPLES: usize = 16;
+
+/// The model of each entry of `PERMUTATIONS`.
+const MODELS: [(&Variant, &[u64; 12]); 3] = [
+    (&XOODOO, &WIDENED),
+    (&XOODOO64, &WIDENED),
+    (&XOODOO64, &LFSR),
+];
+
+/// theta, rho-west and iota, the linear part of a round before chi.
+fn linear_layer<B: Bit>(v: &Variant, a: &[B], round_constant: u64) -> Vec<B> {
+    let a = symbolic::theta(v, a);
+    let a = symbolic::rho_west(v, &a);
+    symbolic::iota(v, &a, round_constant)
+}
+
+/// The scalar permutations agree with both models for every round count.
+fn check_backends(rng: &mut Rng) -> Vec<(String, bool)> {
+    let mut results = Vec::new();
+    for ((name, f), (v, constants)) in PERMUTATIONS.into_iter().zip(MODELS) {
+        let mut bit_level = true;
+        let mut word_level = true;
+        for rounds in 1..=constants.len() {
+            let round_constants = &constants[constants.len() - rounds..];
+            for _ in 0..SAMPLES {
+                let input = random_state(rng);
+                let mut expected = input;
+                f(&mut expected, rounds);
+
+                let bits = symbolic::permute(v, &symbolic::from_bytes(&input), round_constants);
+                bit_level &= symbolic::to_bytes(&bits) == expected;
+
+                let mut a = v.load(&input);
+                v.permute(&mut a, round_constants);
+                word_level &= v.store(&a) == expected;
+            }
+        }
+        results.push((
+            format!("{name} matches the bit-level model at 1 to 12 rounds"),
+            bit_level,
+        ));
+        results.push((
+            format!("{name} matches the word-level model at 1 to 12 rounds"),
+            word_level,
+        ));
+    }
+    results
+}
+
+type Step = fn(&mut [u8; 48]);
+type ModelStep = fn(&Variant, &[bool]) -> Vec<bool>;
+type Batch = fn(&mut [[u8; 48]]);
+
+/// The round constant the iota steps are checked with.
+const ROUND_KEY: u32 = 0x12c;
+
+/// Each function of `steps`, next to the bit-level step it implements.
+const STEPS: [(&Variant, &str, Step, ModelStep); 10] = [
+    (
+        &XOODOO,
+        "theta",
+        |b| on_xoodoo(b, steps::xoodoo::theta),
+        symbolic::theta,
+    ),
+    (
+        &XOODOO,
+        "rho_west",
+        |b| on_xoodoo(b, steps::xoodoo::rho_west),
+        symbolic::rho_west,
+    ),
+    (
+        &XOODOO,
+        "iota",
+        |b| on_xoodoo(b, |a| steps::xoodoo::iota(a, ROUND_KEY)),
+        |v, a| symbolic::iota(v, a, ROUND_KEY as u64),
+    ),
+    (
+        &XOODOO,
+        "chi",
+        |b| on_xoodoo(b, steps::xoodoo::chi),
+        symbolic::chi,
+    ),
+    (
+        &XOODOO,
+        "rho_east",
+        |b| on_xoodoo(b, steps::xoodoo::rho_east),
+        symbolic::rho_east,
+    ),
+    (
+        &XOODOO64,
+        "theta",
+        |b| on_xoodoo64(b, steps::xoodoo64::theta),
+        symbolic::theta,
+    ),
+    (
+        &XOODOO64,
+        "rho_west",
+        |b| on_xoodoo64(b, steps::xoodoo64::rho_west),
+        symbolic::rho_west,
+    ),
+    (
+        &XOODOO64,
+        "iota",
+        |b| on_xoodoo64(b, |a| steps::xoodoo64::iota(a, ROUND_KEY as u64)),
+        |v, a| symbolic::iota(v, a, ROUND_KEY as u64),
+    ),
+    (
+        &XOODOO64,
+        "chi",
+        |b| on_xoodoo64(b, steps::xoodoo64::chi),
+        symbolic::chi,
+    ),
+    (
+        &XOODOO64,
+        "rho_east",
+        |b| on_xoodoo64(b, steps::xoodoo64::rho_east),
+        symbolic::rho_east,
+    ),
+];
+
+fn on_xoodoo(bytes: &mut [u8; 48], f: impl Fn(&mut steps::xoodoo::State)) {
+    let mut a = steps::xoodoo::load(bytes);
+    f(&mut a);
+    *bytes = steps::xoodoo::store(&a);
+}
+
+fn on_xoodoo64(bytes: &mut [u8; 48], f: impl Fn(&mut steps::xoodoo64::State)) {
+    let mut a = steps::xoodoo64::load(bytes);
+    f(&mut a);
+    *bytes = steps::xoodoo64::store(&a);
+}
+
+/// Every step matches the bit-level model, and composing rounds gives the
+/// scalar permutations.
+fn check_steps(rng: &mut Rng) -> Vec<(String, bool)> {
+    let mut results = Vec::new();
+    for (v, name, step, model) in STEPS {
+        let ok = (0..SAMPLES).all(|_| {
+            let mut bytes = random_state(rng);
+            let expected = symbolic::to_bytes(&model(v, &symbolic::from_bytes(&bytes)));
+            step(&mut bytes);
+            bytes == expected
+        });
+        results.push((
+            format!("steps::{}::{name} matches the bit-level model", v.name),
+            ok,
+        ));
+    }
+
+    let mut xoodoo = true;
+    let mut xoodoo64 = true;
+    for _ in 0..SAMPLES {
+        let input = random_state(rng);
+        let mut expected = input;
+        crate::xoodoo_scalar(&mut expected);
+        let mut a = steps::xoodoo::load(&input);
+        steps::xoodoo::permute(&mut a, &crate::ROUND_KEYS);
+        xoodoo &= steps::xoodoo::store(&a) == expected;
+
+        let mut expected = input;
+        crate::xoodoo64_scalar(&mut expected);
+        let mut a = steps::xoodoo64::load(&input);
+        steps::xoodoo64::permute(&mut a, &WIDENED);
+        xoodoo64 &= steps::xoodoo64::store(&a) == expected;
+    }
+    results.push((
+        "steps::xoodoo rounds compose to xoodoo_scalar".to_string(),
+        xoodoo,
+    ));
+    results.push((
+        "steps::xoodoo64 rounds compose to xoodoo64_scalar".to_string(),
+        xoodoo64,
+    ));
+    results
+}
+
+/// `permute_many` gives the same result as permuting one state at a time, for
+/// every remainder modulo the widest backend.
+fn check_permute_many(rng: &mut Rng) -> Vec<(String, bool)> {
+    let cases: [(&str, Batch, Step); 2] = [
+        (
+            "xoodoo_permute_many",
+            crate::xoodoo_permute_many,
+            crate::xoodoo_scalar,
+        ),
+        (
+            "xoodoo64_permute_many",
+            crate::xoodoo64_permute_many,
+            crate::xoodoo64_scalar,
+        ),
+    ];
+    cases
+        .into_iter()
+        .map(|(name, many, one)| {
+            let ok = (0..=9).all(|count| {
+                let inputs: Vec<[u8; 48]> = (0..count).map(|_| random_state(rng)).collect();
+                let mut states = inputs.clone();
+                many(&mut states);
+                inputs.into_iter().zip(&states).all(|(mut input, state)| {
+                    one(&mut input);
+                    input == *state
+                })
+            });
+            (format!("{name} matches one state at a time"), ok)
+        })
+        .collect()
+}
+
+/// Permuting interleaved states twice and deinterleaving them gives the same as
+/// permuting each state twice.
+fn check_interleaved<const N: usize>(rng: &mut Rng) -> Vec<(String, bool)> {
+    let inputs: [[u8; 48]; N] = std::array::from_fn(|_| random_state(rng));
+    let twice = |f: fn(&mut [u8; 48])| {
+        inputs.map(|mut state| {
+            f(&mut state);
+            f(&mut state);
+            state
+        })
+    };
+
+    let mut x = scalar::xoodoo_interleave(&inputs);
+    crate::xoodoo_permute_interleaved(&mut x);
+    crate::xoodoo_permute_interleaved(&mut x);
+    let mut xoodoo = [[0u8; 48]; N];
+    scalar::xoodoo_deinterleave(&x, &mut xoodoo);
+
+    let mut x = scalar::xoodoo64_interleave(&inputs);
+    crate::xoodoo64_permute_interleaved(&mut x);
+    crate::xoodoo64_permute_interleaved(&mut x);
+    let mut xoodoo64 = [[0u8; 48]; N];
+    scalar::xoodoo64_deinterleave(&x, &mut xoodoo64);
+
+    vec![
+        (
+            format!("xoodoo_permute_interleaved::<{N}> matches xoodoo_scalar"),
+            xoodoo == twice(crate::xoodoo_scalar),
+        ),
+        (
+            format!("xoodoo64_permute_interleaved::<{N}> matches xoodoo64_scalar"),
+            xoodoo64 == twice(crate::xoodoo64_scalar),
+        ),
+    ]
+}
+
+/// Splitting lanes into even and odd bits is undone by joining them, and the
+/// bit-interleaved permutation matches `xoodoo64_scalar` at every round count,
+/// with both round constant schedules.
+fn check_bit_interleaved(rng: &mut Rng) -> Vec<(String, bool)> {
+    let split_join = (0..SAMPLES).all(|_| {
+        let x = rng.next_u64();
+        let [even, odd] = bitinterleaved::split(x);
+        let bits_ok = (0..32).all(|i| even >> i & 1 == (x >> (2 * i) & 1) as u32)
+            && (0..32).all(|i| odd >> i & 1 == (x >> (2 * i + 1) & 1) as u32);
+        bits_ok && bitinterleaved::join([even, odd]) == x
+    });
+    let mut results = vec![(
+        "bitinterleaved::split takes even and odd bits and join undoes it".to_string(),
+        split_join,
+    )];
+    let schedules: [(&str, &[u64; 12], &[bitinterleaved::Lane; 12]); 2] = [
+        ("widened", &WIDENED, &bitinterleaved::WIDENED),
+        ("lfsr", &LFSR, &bitinterleaved::LFSR),
+    ];
+    for (name, constants, split) in schedules {
+        let ok = (1..=12).all(|rounds| {
+            (0..SAMPLES).all(|_| {
+                let input = random_state(rng);
+                let (mut expected, mut state) = (input, input);
+                crate::xoodoo64_scalar_rounds(&mut expected, &constants[12 - rounds..]);
+                crate::xoodoo64_bitinterleaved_rounds(&mut state, &split[12 - rounds..]);
+                state == expected
+            })
+        });
+        results.push((
+            format!("xoodoo64_bitinterleaved matches xoodoo64_scalar with {name} constants at 1 to 12 rounds"),
+            ok,
+        ));
+    }
+    results
+}
+
+/// Packing and unpacking round-trips, and the bitsliced permutations match the
+/// scalar ones on every instance, including when not all instances are used.
+fn check_bitsliced<S: bitsliced::Slice>(rng: &mut Rng) -> Vec<(String, bool)> {
+    let n = S::INSTANCES;
+    type Bitsliced<S> = fn(&mut bitsliced::State<S>, &[u64]);
+    let cases: [(&str, Bitsliced<S>, Step); 2] = [
+        ("xoodoo", bitsliced::xoodoo, crate::xoodoo_scalar),
+        ("xoodoo64", bitsliced::xoodoo64, crate::xoodoo64_scalar),
+    ];
+    let mut results = Vec::new();
+    for (name, bitsliced, scalar) in cases {
+        let ok = [n, n / 2 + 3].into_iter().all(|count| {
+            let inputs: Vec<[u8; 48]> = (0..count).map(|_| random_state(rng)).collect();
+            let mut a = bitsliced::pack::<S>(&inputs);
+            let mut states = vec![[0u8; 48]; count];
+            bitsliced::unpack(&a, &mut states);
+            let round_trip = states == inputs;
+            bitsliced(&mut a, &WIDENED);
+            bitsliced::unpack(&a, &mut states);
+            round_trip
+                && inputs.into_iter().zip(&states).all(|(mut input, state)| {
+                    scalar(&mut input);
+                    input == *state
+                })
+        });
+        results.push((format!("{name} bitsliced x{n} matches {name}_scalar"), ok));
+    }
+    results
+}
+
+/// Fused permutations and squeezing give the same as separate calls.
+fn check_fused(rng: &mut Rng) -> Vec<(String, bool)> {
+    type KTimes = fn(&mut [[u8; 48]; 2], usize);
+    type Squeeze = fn(&mut [u8; 48], usize, &mut [u8]);
+    let cases: [(&str, Step, KTimes, Squeeze); 2] = [
+        (
+            "xoodoo",
+            crate::xoodoo_scalar,
+            crate::xoodoo_permute_k_times,
+            crate::xoodoo_squeeze,
+        ),
+        (
+            "xoodoo64",
+            crate::xoodoo64_scalar,
+            crate::xoodoo64_permute_k_times,
+            crate::xoodoo64_squeeze,
+        ),
+    ];
+    let mut results = Vec::new();
+    for (name, one, k_times, squeeze) in cases {
+        let inputs = [random_state(rng), random_state(rng)];
+        let mut fused = inputs;
+        k_times(&mut fused, 5);
+        let separate = inputs.map(|mut state| {
+            (0..5).for_each(|_| one(&mut state));
+            state
+        });
+        results.push((
+            format!("{name}_permute_k_times matches separate calls"),
+            fused == separate,
+        ));
+
+        // 100 bytes at rate 24: four full blocks and a partial one.
+        let (mut fused, mut separate) = (inputs[0], inputs[0]);
+        let mut out = [0u8; 100];
+        squeeze(&mut fused, 24, &mut out);
+        let ok = out.chunks(24).all(|block| {
+            one(&mut separate);
+            block == &separate[..block.len()]
+        });
+        results.push((
+            format!("{name}_squeeze matches separate calls"),
+            ok && fused == separate,
+        ));
+    }
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("sha3") {
+        let inputs: [[u8; 48]; 4] = std::array::from_fn(|_| random_state(rng));
+        let mut fused = inputs;
+        unsafe { crate::xoodoo64_aarch64_sha3_permute_k_times(&mut fused, 5) };
+        let separate = inputs.map(|mut state| {
+            (0..5).for_each(|_| crate::xoodoo64_scalar(&mut state));
+            state
+        });
+        results.push((
+            "xoodoo64_aarch64_sha3_permute_k_times matches separate calls".to_string(),
+            fused == separate,
+        ));
+    }
+    results
+}
+
+/// One round of each scalar permutation on every single-bit state in plane 0
+/// matches the bit-level model. Theta adds the parity of lane `x - 1` to lane
+/// `x`; taking it from lane `x + 1` instead fails here for every input.
+fn check_theta_direction() -> Vec<(String, bool)> {
+    PERMUTATIONS
+        .into_iter()
+        .zip(MODELS)
+        .map(|((name, f), (v, constants))| {
+            let ok = (0..128).all(|i| {
+                let mut input = [0u8; 48];
+                input[i / 8] = 1 << (i % 8);
+                let mut output = input;
+                f(&mut output, 1);
+                let bits = symbolic::permute(v, &symbolic::from_bytes(&input), &constants[11..]);
+                symbolic::to_bytes(&bits) == output
+            });
+            (
+                format!("{name} theta takes the parity of the lane to the west"),
+                ok,
+            )
+        })
+        .collect()
+}
+
+/// Checks each backend, lane by lane, against the model its name refers to.
+struct BackendCheck<'a> {
+    rng: &'a mut Rng,
+    results: Vec<(String, bool)>,
+}
+
+impl BackendVisitor for BackendCheck<'_> {
+    fn visit<const N: usize>(
+        &mut self,
+        name: &str,
+        _parallelism: usize,
+        f: impl Fn(&mut [u8; N]) + Sync,
+    ) {
+        let v = if name.starts_with("xoodoo64") {
+            &XOODOO64
+        } else {
+            &XOODOO
+        };
+        let constants = if name.contains("lfsr") {
+            &LFSR
+        } else {
+            &WIDENED
+        };
+        let mut ok = true;
+        for _ in 0..SAMPLES {
+            let mut states = [0u8; N];
+            self.rng.fill(&mut states);
+            let inputs = states;
+            f(&mut states);
+            for (input, output) in inputs.chunks_exact(48).zip(states.chunks_exact(48)) {
+                let bits = symbolic::from_bytes(input.try_into().unwrap());
+                ok &= symbolic::to_bytes(&symbolic::permute(v, &bits, constants)) == output;
+            }
+        }
+        self.results
+            .push((format!("{name} matches the bit-level model"), ok));
+    }
+}
+
+/// The linear layer on `Linear` variables evaluates like the concrete one, and
+/// maps input differences to output differences.
+fn check_linear(v: &Variant, rng: &mut Rng) -> Vec<(String, bool)> {
+    let round_constant = rng.next_u64() & v.mask();
+    let vars: Vec<Linear> = (0..v.bits()).map(Linear::var).collect();
+    let expressions = linear_layer(v, &vars, round_constant);
+    let mut linear = true;
+    let mut difference = true;
+    for _ in 0..SAMPLES {
+        let a = symbolic::from_bytes(&random_state(rng));
+        let b = symbolic::from_bytes(&random_state(rng));
+        let fa = linear_layer(v, &a, round_constant);
+        let fb = linear_layer(v, &b, round_constant);
+        linear &= expressions.iter().zip(&fa).all(|(e, &x)| e.eval(&a) == x);
+
+        let delta: Vec<Difference> = a.iter().zip(&b).map(|(x, y)| Difference(x ^ y)).collect();
+        let propagated = linear_layer(v, &delta, round_constant);
+        difference &= propagated
+            .iter()
+            .zip(fa.iter().zip(&fb))
+            .all(|(d, (x, y))| d.0 == x ^ y);
+    }
+    vec![
+        (
+            format!("{} linear expressions evaluate correctly", v.name),
+            linear,
+        ),
+        (
+            format!("{} differences propagate correctly", v.name),
+            difference,
+        ),
+    ]
+}
+
+pub fn main(args: &[String]) {
+    if !args.is_empty() {
+        crate::usage();
+    }
+    let mut rng = Rng(1);
+    let mut results = check_backends(&mut rng);
+    results.extend(check_theta_direction());
+    let mut backends = BackendCheck {
+        rng: &mut rng,
+        results: Vec::new(),
+    };
+    for_each_backend(&mut backends);
+    results.extend(backends.results);
+    results.extend(check_steps(&mut rng));
+    results.extend(check_permute_many(&mut rng));
+    results.extend(check_interleaved::<2>(&mut rng));
+    results.extend(check_interleaved::<3>(&mut rng));
+    results.extend(check_fused(&mut rng));
+    results.extend(check_bit_interleaved(&mut rng));
+    results.extend(check_bitsliced::<u64>(&mut rng));
+    results.extend(check_bitsliced::<u128>(&mut rng));
+    results.extend(check_bitsliced::<bitsliced::U64x4>(&mut rng));
+    for v in &VARIANTS {
+        results.extend(check_linear(v, &mut rng));
+    }
+    let mut failed = 0;
+    for (name, ok) in &results {
+        println!("{:<6} {name}", if *ok { "ok" } else { "FAILED" });
+        failed += !ok as usize;
+    }
+    if failed > 0 {
+        println!("{failed} of {} checks failed", results.len());
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace;
+
+    fn assert_ok(results: Vec<(String, bool)>) {
+        let failed: Vec<&String> = results
+            .iter()
+            .filter(|(_, ok)| !ok)
+            .map(|(name, _)| name)
+            .collect();
+        assert!(failed.is_empty(), "failed: {failed:#?}");
+    }
+
+    #[test]
+    fn scalar_permutations_match_models() {
+        assert_ok(check_backends(&mut Rng(1)));
+    }
+
+    #[test]
+    fn theta_direction() {
+        assert_ok(check_theta_direction());
+    }
+
+    #[test]
+    fn backends_match_model() {
+        let mut backends = BackendCheck {
+            rng: &mut Rng(1),
+            results: Vec::new(),
+        };
+        for_each_backend(&mut backends);
+        assert_ok(backends.results);
+    }
+
+    /// Traces each backend on its own, as `trace <backend>` does, and checks
+    /// the last step against the backend's output.
+    struct TraceEach(Vec<(String, bool)>);
+
+    impl BackendVisitor for TraceEach {
+        fn visit<const N: usize>(
+            &mut self,
+            name: &str,
+            parallelism: usize,
+            f: impl Fn(&mut [u8; N]) + Sync,
+        ) {
+            let traced = trace::traceable();
+            let Some(&(_, _, width, traced)) = traced.iter().find(|(n, ..)| *n == name) else {
+                self.0.push((format!("{name} is traceable"), false));
+                return;
+            };
+            let steps = trace::trace_one(traced, width, 1);
+            let mut states: [u8; N] = trace::inputs(parallelism, 1).try_into().unwrap();
+            f(&mut states);
+            let ok = width == parallelism
+                && steps.len() == 5 * WIDENED.len()
+                && steps
+                    .last()
+                    .is_some_and(|(_, _, bytes)| bytes[..] == states[..48]);
+            self.0.push((format!("{name} traces to its output"), ok));
+        }
+    }
+
+    #[test]
+    fn trace_each_backend() {
+        let mut traces = TraceEach(Vec::new());
+        for_each_backend(&mut traces);
+        assert_ok(traces.0);
+    }
+
+    #[test]
+    fn steps_match_model() {
+        assert_ok(check_steps(&mut Rng(1)));
+    }
+
+    #[test]
+    fn permute_many() {
+        assert_ok(check_permute_many(&mut Rng(1)));
+    }
+
+    #[test]
+    fn interleaved() {
+        assert_ok(check_interleaved::<2>(&mut Rng(1)));
+        assert_ok(check_interleaved::<3>(&mut Rng(1)));
+    }
+
+    #[test]
+    fn fused() {
+        assert_ok(check_fused(&mut Rng(1)));
+    }
+
+    #[test]
+    fn bit_interleaved() {
+        assert_ok(check_bit_interleaved(&mut Rng(1)));
+    }
+
+    #[test]
+    fn bitsliced() {
+        assert_ok(check_bitsliced::<u64>(&mut Rng(1)));
+        assert_ok(check_bitsliced::<u128>(&mut Rng(1)));
+        assert_ok(check_bitsliced::<bitsliced::U64x4>(&mut Rng(1)));
+    }
+
+    #[test]
+    fn linear() {
+        for v in &VARIANTS {
+            assert_ok(check_linear(v, &mut Rng(1)));
+        }
+    }
+}
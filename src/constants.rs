@@ -0,0 +1,66 @@
+//! Round constant schedules for Xoodoo64.
+//!
+//! The Xoodoo64 backends widen Xoodoo's 32-bit `ROUND_KEYS`, so only the low
+//! ten bits of one lane ever get a constant. The `lfsr` schedule uses Keccak's
+//! round constant generator instead, which reaches the top of the lane but
+//! only ever sets bits 0, 1, 3, 7, 15, 31 and 63.
+
+use crate::ROUND_KEYS;
+
+/// Xoodoo's round constants, zero-extended to 64 bits.
+pub const WIDENED: [u64; 12] = widen(ROUND_KEYS);
+
+/// Round constants from Keccak's LFSR; see `lfsr`.
+pub const LFSR: [u64; 12] = lfsr();
+
+const fn widen(keys: [u32; 12]) -> [u64; 12] {
+    let mut out = [0; 12];
+    let mut i = 0;
+    while i < 12 {
+        out[i] = keys[i] as u64;
+        i += 1;
+    }
+    out
+}
+
+/// Keccak's generator: bit 2^j - 1 of the constant of round i is output 7i + j
+/// of the LFSR with polynomial x^8 + x^6 + x^5 + x^4 + 1, so the constants
+/// touch bits 0, 1, 3, 7, 15, 31 and 63. We take Keccak's rounds 12 to 23, the
+/// constants of the 12-round Keccak-p[1600, 12].
+const fn lfsr() -> [u64; 12] {
+    let mut state: u8 = 1;
+    let mut out = [0; 12];
+    let mut round = 0;
+    while round < 24 {
+        let mut constant = 0u64;
+        let mut j = 0;
+        while j < 7 {
+            if state & 1 == 1 {
+                constant |= 1 << ((1 << j) - 1);
+            }
+            state = if state & 0x80 != 0 {
+                (state << 1) ^ 0x71
+            } else {
+                state << 1
+            };
+            j += 1;
+        }
+        if round >= 12 {
+            out[round - 12] = constant;
+        }
+        round += 1;
+    }
+    out
+}
+
+pub const SCHEDULES: [(&str, &[u64; 12]); 2] = [("widened", &WIDENED), ("lfsr", &LFSR)];
+
+/// Parses a schedule name or a comma-separated list of hex constants.
+pub fn parse(arg: &str) -> Option<Vec<u64>> {
+    if let Some((_, constants)) = SCHEDULES.iter().find(|(name, _)| *name == arg) {
+        return Some(constants.to_vec());
+    }
+    arg.split(',')
+        .map(|c| u64::from_str_radix(c.trim().trim_start_matches("0x"), 16).ok())
+        .collect()
+}
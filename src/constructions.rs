@@ -0,0 +1,216 @@
+//! Message-length sweeps through permutation-based constructions.
+//!
+//! Two workloads bracket the x1/x2/x4 tradeoff:
+//!
+//! * `hash`: a Xoodyak-style sponge hash with a 16-byte rate. Each message is
+//!   inherently sequential, so an N-way backend hashes N independent messages
+//!   in lockstep (multi-buffer hashing).
+//! * `deck`: a Farfalle-style (Xoofff-like) deck function. Every 48-byte input
+//!   block is masked with a rolled key and permuted independently, so an N-way
+//!   backend processes N blocks of the same message at once.
+//!
+//! Both use the full 12-round permutations exposed by the backends, so the
+//! numbers are comparable with the single-permutation benchmark rather than with
+//! the 6-round Xoofff.
+
+use crate::{BackendVisitor, CLOCK_FREQ, for_each_backend};
+use std::hint::black_box;
+use std::time::Instant;
+
+const LENGTHS: [usize; 9] = [
+    16,
+    64,
+    256,
+    1 << 10,
+    4 << 10,
+    16 << 10,
+    64 << 10,
+    256 << 10,
+    1 << 20,
+];
+
+/// Bytes processed per measurement, so that short messages get enough iterations.
+const BYTES_PER_MEASUREMENT: usize = 16 << 20;
+
+const HASH_RATE: usize = 16;
+const DIGEST_LEN: usize = 32;
+const BLOCK_LEN: usize = 48;
+
+/// Hashes `parallelism` copies of `msg` in lockstep and returns the first digest.
+#[inline(always)]
+fn hash<const N: usize>(
+    parallelism: usize,
+    f: &impl Fn(&mut [u8; N]),
+    msg: &[u8],
+) -> [u8; DIGEST_LEN] {
+    let mut state = [0u8; N];
+    let mut blocks = msg.chunks_exact(HASH_RATE);
+    for block in &mut blocks {
+        for lane in state.chunks_exact_mut(BLOCK_LEN).take(parallelism) {
+            for (s, m) in lane.iter_mut().zip(block) {
+                *s ^= m;
+            }
+        }
+        f(&mut state);
+    }
+    // Final block: remaining bytes, pad10*, and a domain separation byte.
+    let tail = blocks.remainder();
+    for lane in state.chunks_exact_mut(BLOCK_LEN).take(parallelism) {
+        for (s, m) in lane.iter_mut().zip(tail) {
+            *s ^= m;
+        }
+        lane[tail.len()] ^= 0x01;
+        lane[BLOCK_LEN - 1] ^= 0x01;
+    }
+    f(&mut state);
+    let mut digest = [0u8; DIGEST_LEN];
+    let n = DIGEST_LEN / HASH_RATE;
+    for (i, chunk) in digest.chunks_exact_mut(HASH_RATE).enumerate() {
+        chunk.copy_from_slice(&state[..HASH_RATE]);
+        // No permutation after the last chunk: its output would be discarded.
+        if i + 1 < n {
+            f(&mut state);
+        }
+    }
+    digest
+}
+
+/// The Xoofff-style mask rolling function on 32-bit lanes, used for both widths.
+#[inline(always)]
+fn roll(mask: &mut [u8; BLOCK_LEN]) {
+    let lane = |i: usize| u32::from_le_bytes(mask[4 * i..4 * i + 4].try_into().unwrap());
+    let new = lane(0) ^ (lane(0) << 13) ^ lane(4).rotate_left(3);
+    mask.copy_within(4.., 0);
+    mask[BLOCK_LEN - 4..].copy_from_slice(&new.to_le_bytes());
+}
+
+/// Compresses `msg` into a deck accumulator, `parallelism` blocks per call to `f`,
+/// and expands one output block.
+#[inline(always)]
+fn deck<const N: usize>(
+    parallelism: usize,
+    f: &impl Fn(&mut [u8; N]),
+    key: &[u8; BLOCK_LEN],
+    msg: &[u8],
+) -> [u8; BLOCK_LEN] {
+    let mut mask = *key;
+    let mut acc = [0u8; BLOCK_LEN];
+    let mut state = [0u8; N];
+    // Pad with a single 0x01 byte so that the last group is a whole number of blocks.
+    let num_blocks = msg.len() / BLOCK_LEN + 1;
+    let mut block = 0;
+    while block < num_blocks {
+        let group = parallelism.min(num_blocks - block);
+        for (j, lane) in state.chunks_exact_mut(BLOCK_LEN).take(group).enumerate() {
+            let start = (block + j) * BLOCK_LEN;
+            let input = &msg[start.min(msg.len())..(start + BLOCK_LEN).min(msg.len())];
+            lane.copy_from_slice(&mask);
+            for (s, m) in lane.iter_mut().zip(input) {
+                *s ^= m;
+            }
+            if input.len() < BLOCK_LEN {
+                lane[input.len()] ^= 0x01;
+            }
+            roll(&mut mask);
+        }
+        f(&mut state);
+        for lane in state.chunks_exact(BLOCK_LEN).take(group) {
+            for (a, s) in acc.iter_mut().zip(lane) {
+                *a ^= s;
+            }
+        }
+        block += group;
+    }
+    state[..BLOCK_LEN].copy_from_slice(&acc);
+    f(&mut state);
+    state[..BLOCK_LEN].try_into().unwrap()
+}
+
+fn print_header(construction: &str) {
+    println!();
+    print!("{:<30}", construction);
+    for len in LENGTHS {
+        let label = if len >= 1 << 20 {
+            format!("{}M", len >> 20)
+        } else if len >= 1 << 10 {
+            format!("{}K", len >> 10)
+        } else {
+            len.to_string()
+        };
+        print!(" {label:>8}");
+    }
+    println!();
+}
+
+/// Measures cycles per message byte, given the bytes processed by one call to `run`.
+fn cpb(bytes_per_call: usize, mut run: impl FnMut()) -> f64 {
+    let iters = (BYTES_PER_MEASUREMENT / bytes_per_call).max(1);
+    let start = Instant::now();
+    for _ in 0..iters {
+        run();
+    }
+    let elapsed = start.elapsed();
+    elapsed.as_secs_f64() * CLOCK_FREQ / (iters * bytes_per_call) as f64
+}
+
+struct HashSweep;
+
+impl BackendVisitor for HashSweep {
+    fn visit<const N: usize>(
+        &mut self,
+        name: &str,
+        parallelism: usize,
+        f: impl Fn(&mut [u8; N]) + Sync,
+    ) {
+        let msg = vec![0x5au8; LENGTHS[LENGTHS.len() - 1]];
+        print!("{name:<30}");
+        for len in LENGTHS {
+            let cpb = cpb(len * parallelism, || {
+                black_box(hash(parallelism, &f, black_box(&msg[..len])));
+            });
+            print!(" {cpb:>8.2}");
+        }
+        println!();
+    }
+}
+
+struct DeckSweep;
+
+impl BackendVisitor for DeckSweep {
+    fn visit<const N: usize>(
+        &mut self,
+        name: &str,
+        parallelism: usize,
+        f: impl Fn(&mut [u8; N]) + Sync,
+    ) {
+        let msg = vec![0x5au8; LENGTHS[LENGTHS.len() - 1]];
+        let key = [0xa5u8; BLOCK_LEN];
+        print!("{name:<30}");
+        for len in LENGTHS {
+            let cpb = cpb(len, || {
+                black_box(deck(parallelism, &f, &key, black_box(&msg[..len])));
+            });
+            print!(" {cpb:>8.2}");
+        }
+        println!();
+    }
+}
+
+pub fn main(args: &[String]) {
+    let (hash, deck) = match args {
+        [] => (true, true),
+        [which] if which == "hash" => (true, false),
+        [which] if which == "deck" => (false, true),
+        _ => crate::usage(),
+    };
+    println!("Assuming CPU frequency is {:.1} GHz", CLOCK_FREQ / 1e9);
+    println!("Throughput (cpb) by message length");
+    if hash {
+        print_header("hash");
+        for_each_backend(&mut HashSweep);
+    }
+    if deck {
+        print_header("deck");
+        for_each_backend(&mut DeckSweep);
+    }
+}
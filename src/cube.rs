@@ -0,0 +1,114 @@
+//! Algebraic degree estimates and cube testers for the reduced-round scalar
+//! permutations.
+//!
+//! Chi has degree 2 and everything else is linear, so r rounds have degree at
+//! most 2^r. Summing the output over a cube (all assignments to d input bits,
+//! the others fixed) gives zero in every output bit whose degree in those bits
+//! is below d. For each round count and cube dimension we sum over `trials`
+//! random cubes with random fixed bits:
+//!
+//! * the estimated degree is the largest d for which some sum was nonzero;
+//! * a zero-sum distinguisher is an output bit whose sum was zero in every trial
+//!   (a random function does that with probability 2^-trials per bit).
+//!
+//! The maximum distinguishable round count is the largest one with a zero-sum
+//! distinguisher of dimension at most `max_dim`.
+
+use crate::ROUND_KEYS;
+use crate::diffusion::{PERMUTATIONS, Permutation};
+use crate::variant::{Rng, parse_args, random_state};
+
+const BITS: usize = 384;
+
+/// `d` distinct random input bits.
+fn random_cube(rng: &mut Rng, d: usize) -> Vec<usize> {
+    let mut bits: Vec<usize> = (0..BITS).collect();
+    for i in 0..d {
+        let j = i + rng.below((BITS - i) as u64) as usize;
+        bits.swap(i, j);
+    }
+    bits.truncate(d);
+    bits
+}
+
+/// The XOR of the outputs over all assignments to the `cube` bits of `base`,
+/// walking the cube in Gray code order.
+fn cube_sum(f: Permutation, rounds: usize, base: [u8; 48], cube: &[usize]) -> [u8; 48] {
+    let mut input = base;
+    let mut sum = [0u8; 48];
+    for i in 0u64..1 << cube.len() {
+        if i > 0 {
+            let bit = cube[i.trailing_zeros() as usize];
+            input[bit / 8] ^= 1 << (bit % 8);
+        }
+        let mut output = input;
+        f(&mut output, rounds);
+        for (s, o) in sum.iter_mut().zip(output) {
+            *s ^= o;
+        }
+    }
+    sum
+}
+
+/// For one round count and cube dimension: whether any sum was nonzero, and
+/// how many output bits summed to zero in every trial.
+fn test(f: Permutation, rounds: usize, d: usize, trials: usize, rng: &mut Rng) -> (bool, u32) {
+    let mut nonzero = [0u8; 48];
+    for _ in 0..trials {
+        let cube = random_cube(rng, d);
+        let sum = cube_sum(f, rounds, random_state(rng), &cube);
+        for (n, s) in nonzero.iter_mut().zip(sum) {
+            *n |= s;
+        }
+    }
+    let zero_bits = nonzero.iter().map(|n| n.count_zeros()).sum();
+    (nonzero.iter().any(|&n| n != 0), zero_bits)
+}
+
+pub fn main(args: &[String]) {
+    let [max_rounds, max_dim, trials] = parse_args(args, [5, 16, 16]);
+    if !(1..=ROUND_KEYS.len()).contains(&max_rounds) || !(1..=BITS).contains(&max_dim) {
+        crate::usage();
+    }
+
+    println!("Cube sums over {trials} random cubes per dimension, up to dimension {max_dim}");
+    println!(
+        "{:<20} {:>6} {:>8} {:>8} {:>14} {:>14}",
+        "permutation", "rounds", "bound", "degree", "zero-sum dim", "zero-sum bits"
+    );
+    for (name, f) in PERMUTATIONS {
+        let mut rng = Rng(1);
+        let mut distinguished = 0;
+        for rounds in 1..=max_rounds {
+            let bound = 1usize.checked_shl(rounds as u32).unwrap_or(usize::MAX);
+            let mut degree = None;
+            let mut zero_sum = None;
+            for d in 1..=max_dim {
+                let (nonzero, zero_bits) = test(f, rounds, d, trials, &mut rng);
+                if zero_sum.is_none() && zero_bits > 0 {
+                    zero_sum = Some((d, zero_bits));
+                }
+                if nonzero {
+                    degree = Some(d);
+                }
+            }
+            if zero_sum.is_some() {
+                distinguished = rounds;
+            }
+            let degree = match degree {
+                Some(d) if d == max_dim => format!(">={d}"),
+                Some(d) => d.to_string(),
+                None => "0".to_string(),
+            };
+            let (dim, bits) = zero_sum.map_or(("-".to_string(), "-".to_string()), |(d, b)| {
+                (d.to_string(), b.to_string())
+            });
+            println!(
+                "{:<20} {:>6} {:>8} {:>8} {:>14} {:>14}",
+                name, rounds, bound, degree, dim, bits
+            );
+        }
+        println!("{name}: zero-sum distinguishers up to {distinguished} rounds");
+        println!();
+    }
+}
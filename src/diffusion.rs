@@ -0,0 +1,114 @@
+//! Diffusion and avalanche statistics of the scalar permutations by round count.
+//!
+//! For random inputs, every input bit is flipped in turn and the output
+//! differences are tallied per (input bit, output bit) pair. From the tallies:
+//!
+//! * the observed dependency matrix: the fraction of pairs where the output bit
+//!   changed at least once, and the first round count where all of them did
+//!   (full diffusion);
+//! * the avalanche: the mean number of output bits flipped by one input bit,
+//!   against 192 for a random permutation;
+//! * the Strict Avalanche Criterion: every pair should flip with probability
+//!   1/2. We report the largest deviation and the fraction of pairs within three
+//!   standard deviations of 1/2.
+//!
+//! Reduced-round permutations use the last round constants, as Xoodoo does.
+
+use crate::constants::{LFSR, WIDENED};
+use crate::variant::{Rng, parse_args, random_state};
+use crate::{ROUND_KEYS, xoodoo_scalar_rounds, xoodoo64_scalar_rounds};
+
+const BITS: usize = 384;
+
+/// A permutation reduced to the given number of rounds.
+pub type Permutation = fn(&mut [u8; 48], usize);
+
+pub const PERMUTATIONS: [(&str, Permutation); 3] = [
+    ("xoodoo_scalar", |x, rounds| {
+        xoodoo_scalar_rounds(x, &ROUND_KEYS[ROUND_KEYS.len() - rounds..])
+    }),
+    ("xoodoo64_scalar", |x, rounds| {
+        xoodoo64_scalar_rounds(x, &WIDENED[WIDENED.len() - rounds..])
+    }),
+    ("xoodoo64_scalar_lfsr", |x, rounds| {
+        xoodoo64_scalar_rounds(x, &LFSR[LFSR.len() - rounds..])
+    }),
+];
+
+struct Stats {
+    dependencies: f64,
+    avalanche: f64,
+    sac_max_deviation: f64,
+    sac_within: f64,
+}
+
+fn measure(f: Permutation, rounds: usize, samples: usize, rng: &mut Rng) -> Stats {
+    // flips[i * BITS + j]: how often flipping input bit i flipped output bit j.
+    let mut flips = vec![0u32; BITS * BITS];
+    let mut total_flips = 0u64;
+    for _ in 0..samples {
+        let input = random_state(rng);
+        let mut output = input;
+        f(&mut output, rounds);
+        for i in 0..BITS {
+            let mut flipped = input;
+            flipped[i / 8] ^= 1 << (i % 8);
+            f(&mut flipped, rounds);
+            let row = &mut flips[i * BITS..(i + 1) * BITS];
+            for (k, (a, b)) in output.iter().zip(&flipped).enumerate() {
+                let mut diff = a ^ b;
+                total_flips += diff.count_ones() as u64;
+                while diff != 0 {
+                    row[8 * k + diff.trailing_zeros() as usize] += 1;
+                    diff &= diff - 1;
+                }
+            }
+        }
+    }
+    let n = samples as f64;
+    let tolerance = 3.0 * (0.25 / n).sqrt();
+    let deviations = flips.iter().map(|&c| (c as f64 / n - 0.5).abs());
+    Stats {
+        dependencies: flips.iter().filter(|&&c| c > 0).count() as f64 / flips.len() as f64,
+        avalanche: total_flips as f64 / (n * BITS as f64),
+        sac_max_deviation: deviations.clone().fold(0.0, f64::max),
+        sac_within: deviations.filter(|&d| d <= tolerance).count() as f64 / flips.len() as f64,
+    }
+}
+
+pub fn main(args: &[String]) {
+    let [max_rounds, samples] = parse_args(args, [6, 1000]);
+    if !(1..=ROUND_KEYS.len()).contains(&max_rounds) || samples == 0 {
+        crate::usage();
+    }
+
+    println!("{samples} random inputs, every input bit flipped");
+    println!(
+        "{:<20} {:>6} {:>13} {:>10} {:>15} {:>15}",
+        "permutation", "rounds", "dependencies", "avalanche", "SAC max |p-1/2|", "SAC within 3sd"
+    );
+    for (name, f) in PERMUTATIONS {
+        let mut rng = Rng(1);
+        let mut full = None;
+        for rounds in 1..=max_rounds {
+            let stats = measure(f, rounds, samples, &mut rng);
+            if stats.dependencies == 1.0 && full.is_none() {
+                full = Some(rounds);
+            }
+            println!(
+                "{:<20} {:>6} {:>12.2}% {:>10.2} {:>15.4} {:>14.2}%",
+                name,
+                rounds,
+                100.0 * stats.dependencies,
+                stats.avalanche,
+                stats.sac_max_deviation,
+                100.0 * stats.sac_within
+            );
+        }
+        match full {
+            Some(rounds) => println!("{name}: full diffusion after {rounds} rounds"),
+            None => println!("{name}: no full diffusion within {max_rounds} rounds"),
+        }
+        println!();
+    }
+}
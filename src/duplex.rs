@@ -0,0 +1,75 @@
+//! Latency of a duplex stream, as in single-stream Xoodyak encryption.
+//!
+//! The plain latency benchmark permutes back-to-back, which leaves out the work
+//! that sits on the critical path between permutations in a real duplex: XORing
+//! the next message block into the rate, padding it, and extracting the output
+//! (for encryption the ciphertext is the new rate, so this is a copy out of the
+//! state). An N-way backend runs N independent streams, one per state.
+
+use crate::{BackendVisitor, ITERS, for_each_backend, latency};
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Xoodyak's keyed output rate.
+pub const RATE: usize = 24;
+/// Size of the message and output ring buffers, in blocks.
+const RING_BLOCKS: usize = 64;
+
+#[inline(never)]
+fn duplex_latency<const N: usize>(parallelism: usize, f: &impl Fn(&mut [u8; N])) -> f64 {
+    let msg = black_box([0x5au8; RATE * RING_BLOCKS]);
+    // One output ring per stream.
+    let mut out = vec![0u8; parallelism * RATE * RING_BLOCKS];
+    let mut x = black_box([0u8; N]);
+    let start = Instant::now();
+    for i in 0..ITERS {
+        let offset = (i % RING_BLOCKS) * RATE;
+        let block = &msg[offset..offset + RATE];
+        let streams = x.chunks_exact_mut(48).take(parallelism);
+        for (lane, out) in streams.zip(out.chunks_exact_mut(RATE * RING_BLOCKS)) {
+            for (s, m) in lane.iter_mut().zip(block) {
+                *s ^= m;
+            }
+            lane[RATE] ^= 0x01;
+            lane[47] ^= 0x80;
+            out[offset..offset + RATE].copy_from_slice(&lane[..RATE]);
+        }
+        f(&mut x);
+    }
+    black_box((x, out));
+    let elapsed = start.elapsed();
+    elapsed.as_nanos() as f64 / ITERS as f64
+}
+
+struct Duplex;
+
+impl BackendVisitor for Duplex {
+    fn visit<const N: usize>(
+        &mut self,
+        name: &str,
+        parallelism: usize,
+        f: impl Fn(&mut [u8; N]) + Sync,
+    ) {
+        let permute = latency(&f);
+        let duplex = duplex_latency(parallelism, &f);
+        println!(
+            "{:<30} {:4}x {:>16.1} {:>16.1} {:>16.1}",
+            name,
+            parallelism,
+            permute,
+            duplex,
+            duplex - permute
+        );
+    }
+}
+
+pub fn main(args: &[String]) {
+    if !args.is_empty() {
+        crate::usage();
+    }
+    println!(
+        "{:<30} {:>5} {:>16} {:>16} {:>16}",
+        "name", "par", "permute (ns)", "duplex (ns)", "overhead (ns)"
+    );
+    for_each_backend(&mut Duplex);
+}
@@ -0,0 +1,519 @@
+//! DIMACS CNF and algebraic normal form export of the reduced-round permutations.
+//!
+//! Input bit i (numbered as `Variant::bit` does: plane, then lane, then bit) is
+//! variable i + 1. Comment lines list the literal (CNF) or variable (ANF) that
+//! holds each output bit.
+//!
+//! The CNF uses one variable per column parity, theta-effect bit, theta output
+//! bit and chi output bit, and one for each AND in chi; rho only renames
+//! variables and iota only flips literals. The ANF has one variable per state
+//! bit per round and one equation per round output bit, each of degree 2 in the
+//! round's input variables. Equations are written as polynomials equal to zero.
+//!
+//! `--in` and `--out` fix a range of input or output bits to a hex value, bit k
+//! of the value going to the k-th bit of the range, for preimage experiments.
+//! Values with bits set beyond the range are rejected.
+
+use crate::constants;
+use crate::variant::{State, VARIANTS, Variant, ZERO};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+type Lit = i32;
+
+/// Where each bit of the state goes under a bit permutation step.
+fn bit_permutation(v: &Variant, step: impl Fn(&mut State)) -> Vec<usize> {
+    (0..v.bits())
+        .map(|i| {
+            let mut a = ZERO;
+            v.flip_bit(&mut a, i);
+            step(&mut a);
+            (0..v.bits()).find(|&j| v.bit(&a, j)).unwrap()
+        })
+        .collect()
+}
+
+fn permute<T: Clone + Default>(bits: &[T], map: &[usize]) -> Vec<T> {
+    let mut out = vec![T::default(); bits.len()];
+    for (bit, &j) in bits.iter().zip(map) {
+        out[j] = bit.clone();
+    }
+    out
+}
+
+/// The parity columns that feed each theta-effect column.
+fn effect_sources(v: &Variant) -> Vec<Vec<usize>> {
+    let bits = v.lane_bits as usize;
+    let mut sources = vec![Vec::new(); v.columns()];
+    for c in 0..v.columns() {
+        let mut p = [0; 4];
+        p[c / bits] |= 1 << (c % bits);
+        let e = v.theta_effect(&p);
+        for (target, s) in sources.iter_mut().enumerate() {
+            if e[target / bits] >> (target % bits) & 1 == 1 {
+                s.push(c);
+            }
+        }
+    }
+    sources
+}
+
+/// The state bit of plane `y` in column `c`.
+fn index(v: &Variant, y: usize, c: usize) -> usize {
+    y * v.columns() + c
+}
+
+struct Cnf {
+    vars: i32,
+    clauses: Vec<Vec<Lit>>,
+}
+
+impl Cnf {
+    fn var(&mut self) -> Lit {
+        self.vars += 1;
+        self.vars
+    }
+
+    fn xor(&mut self, a: Lit, b: Lit) -> Lit {
+        let y = self.var();
+        self.clauses.extend([
+            vec![-y, a, b],
+            vec![-y, -a, -b],
+            vec![y, -a, b],
+            vec![y, a, -b],
+        ]);
+        y
+    }
+
+    fn xor_all(&mut self, lits: &[Lit]) -> Lit {
+        lits[1..].iter().fold(lits[0], |acc, &l| self.xor(acc, l))
+    }
+
+    /// `!a & b`.
+    fn and_not(&mut self, a: Lit, b: Lit) -> Lit {
+        let y = self.var();
+        self.clauses
+            .extend([vec![-y, -a], vec![-y, b], vec![y, a, -b]]);
+        y
+    }
+}
+
+/// An affine form over GF(2): a set of variables and a constant.
+#[derive(Clone, Default)]
+struct Affine {
+    vars: BTreeSet<usize>,
+    one: bool,
+}
+
+impl Affine {
+    fn add(&mut self, other: &Affine) {
+        self.vars = self
+            .vars
+            .symmetric_difference(&other.vars)
+            .copied()
+            .collect();
+        self.one ^= other.one;
+    }
+}
+
+/// A polynomial over GF(2), as a set of monomials (sorted variable lists).
+#[derive(Default)]
+struct Polynomial(BTreeSet<Vec<usize>>);
+
+impl Polynomial {
+    fn toggle(&mut self, monomial: Vec<usize>) {
+        if !self.0.remove(&monomial) {
+            self.0.insert(monomial);
+        }
+    }
+
+    fn add_affine(&mut self, a: &Affine) {
+        for &x in &a.vars {
+            self.toggle(vec![x]);
+        }
+        if a.one {
+            self.toggle(Vec::new());
+        }
+    }
+
+    fn add_product(&mut self, a: &Affine, b: &Affine) {
+        for &x in &a.vars {
+            for &y in &b.vars {
+                self.toggle(if x == y {
+                    vec![x]
+                } else {
+                    vec![x.min(y), x.max(y)]
+                });
+            }
+        }
+        if a.one {
+            self.add_affine(&Affine {
+                vars: b.vars.clone(),
+                one: b.one,
+            });
+        }
+        if b.one {
+            self.add_affine(&Affine {
+                vars: a.vars.clone(),
+                one: false,
+            });
+        }
+    }
+
+    fn format(&self) -> String {
+        let terms: Vec<String> = self
+            .0
+            .iter()
+            .map(|m| match m.as_slice() {
+                [] => "1".to_string(),
+                _ => m
+                    .iter()
+                    .map(|x| format!("x{x}"))
+                    .collect::<Vec<_>>()
+                    .join("*"),
+            })
+            .collect();
+        if terms.is_empty() {
+            "0".to_string()
+        } else {
+            terms.join(" + ")
+        }
+    }
+}
+
+struct Steps {
+    variant: Variant,
+    rho_west: Vec<usize>,
+    rho_east: Vec<usize>,
+    sources: Vec<Vec<usize>>,
+}
+
+impl Steps {
+    fn new(v: &Variant) -> Steps {
+        Steps {
+            variant: *v,
+            rho_west: bit_permutation(v, |a| v.rho_west(a)),
+            rho_east: bit_permutation(v, |a| v.rho_east(a)),
+            sources: effect_sources(v),
+        }
+    }
+
+    fn constant_bits(&self, constant: u64) -> Vec<bool> {
+        let mut a = ZERO;
+        self.variant.iota(&mut a, constant);
+        (0..self.variant.bits())
+            .map(|i| self.variant.bit(&a, i))
+            .collect()
+    }
+
+    fn cnf_round(&self, cnf: &mut Cnf, a: &[Lit], constant: u64) -> Vec<Lit> {
+        let v = &self.variant;
+        let parity: Vec<Lit> = (0..v.columns())
+            .map(|c| cnf.xor_all(&[a[index(v, 0, c)], a[index(v, 1, c)], a[index(v, 2, c)]]))
+            .collect();
+        let effect: Vec<Lit> = self
+            .sources
+            .iter()
+            .map(|s| cnf.xor_all(&s.iter().map(|&c| parity[c]).collect::<Vec<_>>()))
+            .collect();
+        let theta: Vec<Lit> = (0..v.bits())
+            .map(|i| cnf.xor(a[i], effect[i % v.columns()]))
+            .collect();
+        let mut b = permute(&theta, &self.rho_west);
+        for (l, flip) in b.iter_mut().zip(self.constant_bits(constant)) {
+            if flip {
+                *l = -*l;
+            }
+        }
+        let mut chi = vec![0; v.bits()];
+        for y in 0..3 {
+            for c in 0..v.columns() {
+                let t = cnf.and_not(b[index(v, (y + 1) % 3, c)], b[index(v, (y + 2) % 3, c)]);
+                chi[index(v, y, c)] = cnf.xor(b[index(v, y, c)], t);
+            }
+        }
+        permute(&chi, &self.rho_east)
+    }
+
+    /// The affine chi inputs of a round in terms of its input variables.
+    fn anf_chi_inputs(&self, first_var: usize, constant: u64) -> Vec<Affine> {
+        let v = &self.variant;
+        let a: Vec<Affine> = (0..v.bits())
+            .map(|i| Affine {
+                vars: BTreeSet::from([first_var + i]),
+                one: false,
+            })
+            .collect();
+        let mut theta = a.clone();
+        for (c, s) in self.sources.iter().enumerate() {
+            let mut e = Affine::default();
+            for &p in s {
+                for y in 0..3 {
+                    e.add(&a[index(v, y, p)]);
+                }
+            }
+            for y in 0..3 {
+                theta[index(v, y, c)].add(&e);
+            }
+        }
+        let mut b = permute(&theta, &self.rho_west);
+        for (l, flip) in b.iter_mut().zip(self.constant_bits(constant)) {
+            l.one ^= flip;
+        }
+        b
+    }
+}
+
+/// A range of bits and the value to fix them to.
+struct Fix {
+    bits: std::ops::Range<usize>,
+    value: u128,
+}
+
+fn parse_fix(arg: &str, total: usize) -> Option<Fix> {
+    let (range, value) = arg.split_once('=')?;
+    let bits = match range.split_once("..") {
+        Some((lo, hi)) => lo.parse().ok()?..hi.parse().ok()?,
+        None => {
+            let i: usize = range.parse().ok()?;
+            i..i + 1
+        }
+    };
+    if bits.is_empty() || bits.end > total || bits.len() > 128 {
+        return None;
+    }
+    let value = u128::from_str_radix(value.trim_start_matches("0x"), 16).ok()?;
+    if bits.len() < 128 && value >> bits.len() != 0 {
+        return None;
+    }
+    Some(Fix { bits, value })
+}
+
+fn fixed_bits(fixes: &[Fix]) -> impl Iterator<Item = (usize, bool)> + '_ {
+    fixes.iter().flat_map(|f| {
+        f.bits
+            .clone()
+            .enumerate()
+            .map(|(k, i)| (i, f.value >> k & 1 == 1))
+    })
+}
+
+fn cnf(steps: &Steps, constants: &[u64], inputs: &[Fix], outputs: &[Fix]) -> String {
+    let v = &steps.variant;
+    let mut cnf = Cnf {
+        vars: v.bits() as i32,
+        clauses: Vec::new(),
+    };
+    let mut a: Vec<Lit> = (1..=v.bits() as i32).collect();
+    for &c in constants {
+        a = steps.cnf_round(&mut cnf, &a, c);
+    }
+    for (i, value) in fixed_bits(inputs) {
+        let l = i as Lit + 1;
+        cnf.clauses.push(vec![if value { l } else { -l }]);
+    }
+    for (i, value) in fixed_bits(outputs) {
+        cnf.clauses.push(vec![if value { a[i] } else { -a[i] }]);
+    }
+    let mut out = String::new();
+    writeln!(out, "c {} rounds of {}", constants.len(), v.name).unwrap();
+    writeln!(out, "c input bit i is variable i + 1").unwrap();
+    for (i, l) in a.iter().enumerate() {
+        writeln!(out, "c output bit {i} is literal {l}").unwrap();
+    }
+    writeln!(out, "p cnf {} {}", cnf.vars, cnf.clauses.len()).unwrap();
+    for clause in &cnf.clauses {
+        for l in clause {
+            write!(out, "{l} ").unwrap();
+        }
+        writeln!(out, "0").unwrap();
+    }
+    out
+}
+
+fn anf(steps: &Steps, constants: &[u64], inputs: &[Fix], outputs: &[Fix]) -> String {
+    let v = &steps.variant;
+    let bits = v.bits();
+    let mut out = String::new();
+    writeln!(out, "c {} rounds of {}", constants.len(), v.name).unwrap();
+    writeln!(out, "c input bit i is x{{i + 1}}").unwrap();
+    writeln!(
+        out,
+        "c output bit i is x{{i + {}}}",
+        constants.len() * bits + 1
+    )
+    .unwrap();
+    for (round, &c) in constants.iter().enumerate() {
+        let first = round * bits + 1;
+        let b = steps.anf_chi_inputs(first, c);
+        let mut chi = Vec::with_capacity(bits);
+        for y in 0..3 {
+            for col in 0..v.columns() {
+                // b0 + (b1 + 1) * b2
+                let mut p = Polynomial::default();
+                p.add_affine(&b[index(v, y, col)]);
+                let mut not_b1 = b[index(v, (y + 1) % 3, col)].clone();
+                not_b1.one ^= true;
+                p.add_product(&not_b1, &b[index(v, (y + 2) % 3, col)]);
+                chi.push(p);
+            }
+        }
+        // Rho east only moves the bits, so output bit j of the round is the chi
+        // output that lands there.
+        let mut sources = vec![0; bits];
+        for (i, &j) in steps.rho_east.iter().enumerate() {
+            sources[j] = i;
+        }
+        for (j, &i) in sources.iter().enumerate() {
+            let output = first + bits + j;
+            chi[i].toggle(vec![output]);
+            writeln!(out, "{}", chi[i].format()).unwrap();
+        }
+    }
+    let last = constants.len() * bits + 1;
+    for (first, fixes) in [(1, inputs), (last, outputs)] {
+        for (i, value) in fixed_bits(fixes) {
+            let suffix = if value { " + 1" } else { "" };
+            writeln!(out, "x{}{suffix}", first + i).unwrap();
+        }
+    }
+    out
+}
+
+pub fn main(args: &[String]) {
+    let [format, name, rounds, options @ ..] = args else {
+        crate::usage();
+    };
+    let v = VARIANTS
+        .iter()
+        .find(|v| v.name == name)
+        .unwrap_or_else(|| crate::usage());
+    let rounds: usize = rounds.parse().unwrap_or_else(|_| crate::usage());
+    let mut schedule = constants::WIDENED.to_vec();
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().unwrap_or_else(|| crate::usage());
+        match option.as_str() {
+            "--constants" => schedule = constants::parse(value).unwrap_or_else(|| crate::usage()),
+            "--in" => inputs.push(parse_fix(value, v.bits()).unwrap_or_else(|| crate::usage())),
+            "--out" => outputs.push(parse_fix(value, v.bits()).unwrap_or_else(|| crate::usage())),
+            _ => crate::usage(),
+        }
+    }
+    if rounds == 0 || rounds > schedule.len() {
+        crate::usage();
+    }
+    let constants = &schedule[schedule.len() - rounds..];
+    let steps = Steps::new(v);
+    let text = match format.as_str() {
+        "cnf" => cnf(&steps, constants, &inputs, &outputs),
+        "anf" => anf(&steps, constants, &inputs, &outputs),
+        _ => crate::usage(),
+    };
+    print!("{text}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diffusion::PERMUTATIONS;
+    use crate::variant::{Rng, XOODOO, XOODOO64, random_state};
+
+    /// The output bits of `dimacs`, as its comments name them, after unit
+    /// propagation from its unit clauses. Panics on a conflict or an output
+    /// that propagation leaves open.
+    fn propagate(dimacs: &str) -> Vec<bool> {
+        let mut outputs: Vec<Lit> = Vec::new();
+        let mut clauses: Vec<Vec<Lit>> = Vec::new();
+        let mut values: Vec<Option<bool>> = Vec::new();
+        for line in dimacs.lines() {
+            if let Some(rest) = line.strip_prefix("c output bit ") {
+                outputs.push(rest.split_once(" is literal ").unwrap().1.parse().unwrap());
+            } else if let Some(rest) = line.strip_prefix("p cnf ") {
+                let vars: usize = rest.split_whitespace().next().unwrap().parse().unwrap();
+                values = vec![None; vars + 1];
+            } else if !line.starts_with('c') {
+                let clause: Vec<Lit> = line
+                    .split_whitespace()
+                    .map(|l| l.parse().unwrap())
+                    .collect();
+                assert_eq!(clause.last(), Some(&0));
+                clauses.push(clause[..clause.len() - 1].to_vec());
+            }
+        }
+        let value = |values: &[Option<bool>], l: Lit| {
+            values[l.unsigned_abs() as usize].map(|v| v == (l > 0))
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for clause in &clauses {
+                if clause.iter().any(|&l| value(&values, l) == Some(true)) {
+                    continue;
+                }
+                let open: Vec<Lit> = clause
+                    .iter()
+                    .copied()
+                    .filter(|&l| value(&values, l).is_none())
+                    .collect();
+                match open[..] {
+                    [] => panic!("conflict in {clause:?}"),
+                    [l] => {
+                        values[l.unsigned_abs() as usize] = Some(l > 0);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        outputs
+            .iter()
+            .map(|&l| value(&values, l).expect("output not forced"))
+            .collect()
+    }
+
+    /// With every input bit fixed, unit propagation through the one-round CNF
+    /// computes the scalar permutation.
+    #[test]
+    fn cnf_computes_one_round() {
+        let mut rng = Rng(1);
+        for (v, (_, f)) in [(&XOODOO, PERMUTATIONS[0]), (&XOODOO64, PERMUTATIONS[1])] {
+            let steps = Steps::new(v);
+            let constants = &constants::WIDENED[constants::WIDENED.len() - 1..];
+            for _ in 0..4 {
+                let mut state = random_state(&mut rng);
+                let a = v.load(&state);
+                let inputs: Vec<Fix> = (0..v.bits())
+                    .step_by(128)
+                    .map(|lo| {
+                        let bits = lo..(lo + 128).min(v.bits());
+                        let value = bits
+                            .clone()
+                            .rev()
+                            .fold(0, |acc, i| acc << 1 | v.bit(&a, i) as u128);
+                        Fix { bits, value }
+                    })
+                    .collect();
+                let outputs = propagate(&cnf(&steps, constants, &inputs, &[]));
+                f(&mut state, 1);
+                let b = v.load(&state);
+                let expected: Vec<bool> = (0..v.bits()).map(|i| v.bit(&b, i)).collect();
+                assert_eq!(outputs, expected, "{}", v.name);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_fix_range() {
+        assert_eq!(parse_fix("0..4=f", 384).map(|f| f.value), Some(0xf));
+        assert_eq!(
+            parse_fix("0..128=0xffffffffffffffffffffffffffffffff", 384).map(|f| f.value),
+            Some(u128::MAX)
+        );
+        assert!(parse_fix("0..4=1f", 384).is_none());
+        assert!(parse_fix("3=2", 384).is_none());
+        assert!(parse_fix("380..388=0", 384).is_none());
+    }
+}
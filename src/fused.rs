@@ -0,0 +1,141 @@
+//! Latency saved by keeping the state in registers across permutations.
+//!
+//! The latency benchmark calls a backend back-to-back, and every call loads the
+//! state from bytes and stores it back. `xoodoo_permute_k_times` and
+//! `xoodoo_squeeze` (and their Xoodoo64 versions) load once, permute or squeeze
+//! repeatedly, and store once. For each we report the time per permutation of
+//! separate calls and of the fused loop, and the difference. On aarch64 with
+//! SHA3, `xoodoo64_aarch64_sha3_permute_k_times` is compared the same way with
+//! `xoodoo64_aarch64_sha3_x4`, which transposes the states on every call.
+
+use crate::ITERS;
+use crate::duplex::RATE;
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Output per squeeze measurement, in blocks of `RATE` bytes.
+const SQUEEZE_BLOCKS: usize = 1024;
+
+fn print_row(name: &str, parallelism: usize, separate: f64, fused: f64) {
+    println!(
+        "{:<30} {:4}x {:>16.1} {:>16.1} {:>16.1}",
+        name,
+        parallelism,
+        separate,
+        fused,
+        separate - fused
+    );
+}
+
+#[inline(never)]
+fn per_permutation(run: impl Fn(usize), permutations: usize) -> f64 {
+    let start = Instant::now();
+    run(permutations);
+    start.elapsed().as_nanos() as f64 / permutations as f64
+}
+
+fn k_times<const N: usize>(
+    name: &str,
+    separate: fn(&mut [[u8; 48]; N]),
+    fused: fn(&mut [[u8; 48]; N], usize),
+) {
+    let separate = per_permutation(
+        |k| {
+            let mut x = black_box([[0u8; 48]; N]);
+            for _ in 0..k {
+                separate(&mut x);
+            }
+            black_box(x);
+        },
+        ITERS,
+    );
+    let fused = per_permutation(
+        |k| {
+            let mut x = black_box([[0u8; 48]; N]);
+            fused(&mut x, k);
+            black_box(x);
+        },
+        ITERS,
+    );
+    print_row(name, N, separate, fused);
+}
+
+fn squeeze(name: &str, separate: fn(&mut [u8; 48]), fused: fn(&mut [u8; 48], usize, &mut [u8])) {
+    let calls = ITERS / SQUEEZE_BLOCKS;
+    let separate = per_permutation(
+        |_| {
+            let mut x = black_box([0u8; 48]);
+            let mut out = [0u8; RATE * SQUEEZE_BLOCKS];
+            for _ in 0..calls {
+                for block in out.chunks_exact_mut(RATE) {
+                    separate(&mut x);
+                    block.copy_from_slice(&x[..RATE]);
+                }
+                black_box(&out);
+            }
+        },
+        calls * SQUEEZE_BLOCKS,
+    );
+    let fused = per_permutation(
+        |_| {
+            let mut x = black_box([0u8; 48]);
+            let mut out = [0u8; RATE * SQUEEZE_BLOCKS];
+            for _ in 0..calls {
+                fused(&mut x, RATE, &mut out);
+                black_box(&out);
+            }
+        },
+        calls * SQUEEZE_BLOCKS,
+    );
+    print_row(name, 1, separate, fused);
+}
+
+pub fn main(args: &[String]) {
+    if !args.is_empty() {
+        crate::usage();
+    }
+    println!(
+        "{:<30} {:>5} {:>16} {:>16} {:>16}",
+        "name", "par", "separate (ns)", "fused (ns)", "saved (ns)"
+    );
+    k_times::<1>(
+        "xoodoo_permute_k_times",
+        |s| crate::xoodoo_scalar(&mut s[0]),
+        crate::xoodoo_permute_k_times,
+    );
+    k_times::<2>(
+        "xoodoo_permute_k_times",
+        |s| crate::xoodoo_scalar_x2(s.as_flattened_mut().try_into().unwrap()),
+        crate::xoodoo_permute_k_times,
+    );
+    k_times::<1>(
+        "xoodoo64_permute_k_times",
+        |s| crate::xoodoo64_scalar(&mut s[0]),
+        crate::xoodoo64_permute_k_times,
+    );
+    k_times::<2>(
+        "xoodoo64_permute_k_times",
+        |s| crate::xoodoo64_scalar_x2(s.as_flattened_mut().try_into().unwrap()),
+        crate::xoodoo64_permute_k_times,
+    );
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("sha3") {
+        k_times::<4>(
+            "xoodoo64_neon_sha3_k_times",
+            |s| unsafe {
+                crate::xoodoo64_aarch64_sha3_x4(s.as_flattened_mut().try_into().unwrap())
+            },
+            |s, k| unsafe { crate::xoodoo64_aarch64_sha3_permute_k_times(s, k) },
+        );
+    }
+    squeeze(
+        "xoodoo_squeeze",
+        crate::xoodoo_scalar,
+        crate::xoodoo_squeeze,
+    );
+    squeeze(
+        "xoodoo64_squeeze",
+        crate::xoodoo64_scalar,
+        crate::xoodoo64_squeeze,
+    );
+}
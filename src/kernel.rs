@@ -0,0 +1,226 @@
+//! Column-parity kernel and theta-effect analysis for Xoodoo and Xoodoo64.
+//!
+//! Theta adds to every column the theta-effect E, a linear function of the
+//! column parity plane P. States with P = 0 are in the column-parity kernel and
+//! pass through theta unchanged, so low-weight trails that stay in the kernel
+//! are the main threat the rho offsets have to rule out.
+//!
+//! For states outside the kernel, a column that is odd in P and not in E stays
+//! active through theta, while any column in E becomes active unless it was odd
+//! with all three bits set. The fewest active columns before and after theta,
+//! for a given P, is therefore |P u E| + |P \ E|: the column branch number of
+//! theta restricted to P.
+
+use crate::trails::{Kind, Search};
+use crate::variant::{LinearMap, State, VARIANTS, Variant, ZERO, parse_args};
+
+/// Column `c` of the plane, numbered lane-major.
+fn set_plane_bit(v: &Variant, p: &mut [u64; 4], c: usize) {
+    let bits = v.lane_bits as usize;
+    p[c / bits] |= 1 << (c % bits);
+}
+
+fn weight(p: &[u64; 4]) -> u32 {
+    p.iter().map(|w| w.count_ones()).sum()
+}
+
+/// The rank over GF(2) of a set of planes.
+fn rank(v: &Variant, mut rows: Vec<[u64; 4]>) -> usize {
+    let bits = v.lane_bits as usize;
+    let mut rank = 0;
+    for c in 0..v.columns() {
+        let (x, z) = (c / bits, c % bits);
+        let Some(pivot) = (rank..rows.len()).find(|&r| rows[r][x] >> z & 1 == 1) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        let row = rows[rank];
+        for (r, other) in rows.iter_mut().enumerate() {
+            if r != rank && other[x] >> z & 1 == 1 {
+                for (o, w) in other.iter_mut().zip(row) {
+                    *o ^= w;
+                }
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+/// Calls `visit` with every set of up to `k` columns that contains column 0.
+fn column_sets(v: &Variant, k: usize, visit: &mut impl FnMut(&[usize])) {
+    fn rec(v: &Variant, k: usize, cols: &mut Vec<usize>, visit: &mut impl FnMut(&[usize])) {
+        visit(cols);
+        if cols.len() < k {
+            for c in cols[cols.len() - 1] + 1..v.columns() {
+                cols.push(c);
+                rec(v, k, cols, visit);
+                cols.pop();
+            }
+        }
+    }
+    rec(v, k, &mut vec![0], visit);
+}
+
+pub struct EffectStats {
+    pub patterns: usize,
+    pub min_effect: u32,
+    pub total_effect: u64,
+    pub min_branch: u32,
+}
+
+/// Theta-effect statistics of the parity patterns with `k` odd columns. Every
+/// translation class of patterns has the same number of members containing
+/// column 0, so the mean over those is the mean over all patterns.
+pub fn effect_stats(v: &Variant, k: usize) -> EffectStats {
+    let mut stats = EffectStats {
+        patterns: 0,
+        min_effect: u32::MAX,
+        total_effect: 0,
+        min_branch: u32::MAX,
+    };
+    column_sets(v, k, &mut |cols| {
+        if cols.len() != k {
+            return;
+        }
+        let mut p = [0; 4];
+        for &c in cols {
+            set_plane_bit(v, &mut p, c);
+        }
+        let e = v.theta_effect(&p);
+        let mut union = [0; 4];
+        let mut only_p = [0; 4];
+        for x in 0..4 {
+            union[x] = p[x] | e[x];
+            only_p[x] = p[x] & !e[x];
+        }
+        stats.patterns += 1;
+        stats.min_effect = stats.min_effect.min(weight(&e));
+        stats.total_effect += weight(&e) as u64;
+        stats.min_branch = stats.min_branch.min(weight(&union) + weight(&only_p));
+    });
+    stats
+}
+
+/// The dimension of the space of parity patterns without theta-effect.
+pub fn effect_free_dimension(v: &Variant) -> usize {
+    let planes = (0..v.columns())
+        .map(|c| {
+            let mut p = [0; 4];
+            set_plane_bit(v, &mut p, c);
+            v.theta_effect(&p)
+        })
+        .collect();
+    v.columns() - rank(v, planes)
+}
+
+struct Kernel<'a> {
+    variant: &'a Variant,
+    search: Search,
+    rho_east: LinearMap,
+}
+
+impl Kernel<'_> {
+    /// The lightest chi weight of `steps` rounds that start from the in-kernel
+    /// theta input `a` and reach the kernel again before every theta.
+    fn chain(&self, a: &State, steps: usize) -> Option<u32> {
+        let v = self.variant;
+        let mut b = *a;
+        v.rho_west(&mut b);
+        let w = self.search.weight(&b);
+        if steps == 1 {
+            let mut found = false;
+            self.search.propagate(&b, &self.rho_east, |next| {
+                found |= v.parity(next) == [0; 4];
+            });
+            return found.then_some(w);
+        }
+        let mut best: Option<u32> = None;
+        self.search.propagate(&b, &self.rho_east, |next| {
+            if v.parity(next) == [0; 4]
+                && let Some(rest) = self.chain(next, steps - 1)
+            {
+                best = Some(best.map_or(rest, |b| b.min(rest)));
+            }
+        });
+        best.map(|rest| w + rest)
+    }
+}
+
+/// Calls `visit` with every in-kernel state whose active columns are one of
+/// the sets of `column_sets`.
+fn kernel_states(v: &Variant, k: usize, mut visit: impl FnMut(&State)) {
+    let bits = v.lane_bits as usize;
+    column_sets(v, k, &mut |cols| {
+        // Every assignment of the three even-parity values to the columns.
+        for values in 0..3usize.pow(cols.len() as u32) {
+            let mut s = ZERO;
+            let mut rest = values;
+            for &c in cols {
+                let value = [3, 5, 6][rest % 3];
+                v.set_column(&mut s, c / bits, (c % bits) as u32, value);
+                rest /= 3;
+            }
+            visit(&s);
+        }
+    });
+}
+
+pub fn main(args: &[String]) {
+    let [odd_columns, kernel_columns] = parse_args(args, [3, 2]);
+
+    for v in &VARIANTS {
+        println!(
+            "{}: theta offsets {:?}, lane shift {}",
+            v.name, v.theta_rot, v.theta_shift
+        );
+        let dim = effect_free_dimension(v);
+        println!("  parity patterns without theta-effect: 2^{dim}");
+        println!(
+            "  {:>11} {:>10} {:>12} {:>12} {:>12}",
+            "odd columns", "patterns", "min effect", "mean effect", "min branch"
+        );
+        for k in 1..=odd_columns {
+            let stats = effect_stats(v, k);
+            println!(
+                "  {:>11} {:>10} {:>12} {:>12.2} {:>12}",
+                k,
+                stats.patterns,
+                stats.min_effect,
+                stats.total_effect as f64 / stats.patterns as f64,
+                stats.min_branch
+            );
+        }
+
+        let kernel = Kernel {
+            variant: v,
+            search: Search::new(Kind::Differential, v, u32::MAX),
+            rho_east: LinearMap::new(v, |a| v.rho_east(a)),
+        };
+        let mut states = 0;
+        let mut chains = [0usize; 2];
+        let mut lightest = [None::<u32>; 2];
+        kernel_states(v, kernel_columns, |a| {
+            states += 1;
+            for steps in 1..=2 {
+                let Some(w) = kernel.chain(a, steps) else {
+                    break;
+                };
+                chains[steps - 1] += 1;
+                let l = &mut lightest[steps - 1];
+                *l = Some(l.map_or(w, |l| l.min(w)));
+            }
+        });
+        println!(
+            "  in-kernel states with at most {kernel_columns} active columns, containing column 0: {states}"
+        );
+        for (steps, (n, w)) in chains.iter().zip(lightest).enumerate() {
+            let w = w.map_or("-".to_string(), |w| w.to_string());
+            println!(
+                "  staying in the kernel for {} round(s): {n} (lightest chi weight {w})",
+                steps + 1
+            );
+        }
+        println!();
+    }
+}
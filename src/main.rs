@@ -1,7 +1,41 @@
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::{uint32x4_t, uint64x2_t};
 use std::hint::black_box;
+#[cfg(target_arch = "aarch64")]
 use std::mem::transmute;
 use std::time::Instant;
+use trace::NoTrace;
+#[cfg(target_arch = "aarch64")]
+use trace::{Step, Tracer};
+
+mod bitinterleaved;
+mod bitsliced;
+mod check;
+mod constants;
+mod constructions;
+mod cube;
+mod diffusion;
+mod duplex;
+mod export;
+mod fused;
+mod kernel;
+mod offsets;
+mod perf;
+#[cfg(any(feature = "portable", test))]
+mod portable;
+mod readme;
+mod scalar;
+#[cfg(any(target_arch = "aarch64", feature = "portable", test))]
+mod shuffles;
+mod steps;
+mod symbolic;
+mod symmetry;
+mod threads;
+mod trace;
+mod trails;
+mod variant;
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 fn read128(x: &[u8]) -> [u8; 16] {
     let mut result = [0u8; 16];
@@ -9,6 +43,7 @@ fn read128(x: &[u8]) -> [u8; 16] {
     result
 }
 
+#[cfg(target_arch = "aarch64")]
 #[inline(always)]
 fn write128(x: [u8; 16], y: &mut [u8]) {
     y[0..16].copy_from_slice(&x);
@@ -38,6 +73,12 @@ fn write64(x: u64, y: &mut [u8]) {
     y[0..8].copy_from_slice(&x.to_le_bytes());
 }
 
+/// Views consecutive 48-byte states as an array of `N` states.
+#[inline(always)]
+fn as_states<const N: usize>(bytes: &mut [u8]) -> &mut [[u8; 48]; N] {
+    bytes.as_chunks_mut().0.try_into().unwrap()
+}
+
 const ROUND_KEYS: [u32; 12] = [
     0x00000058, 0x00000038, 0x000003C0, 0x000000D0, 0x00000120, 0x00000014, 0x00000060, 0x0000002C,
     0x00000380, 0x000000F0, 0x000001A0, 0x00000012,
@@ -46,6 +87,13 @@ const ROUND_KEYS: [u32; 12] = [
 #[cfg(target_arch = "aarch64")]
 #[inline(never)]
 fn xoodoo_aarch64(x: &mut [u8; 48]) {
+    xoodoo_aarch64_traced(x, &mut NoTrace);
+}
+
+/// `xoodoo_aarch64`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn xoodoo_aarch64_traced(x: &mut [u8; 48], tracer: &mut impl Tracer) {
     use std::arch::aarch64::*;
 
     unsafe {
@@ -53,31 +101,31 @@ fn xoodoo_aarch64(x: &mut [u8; 48]) {
         let mut b: uint32x4_t = transmute(read128(&x[16..32]));
         let mut c: uint32x4_t = transmute(read128(&x[32..48]));
 
-        let rho_west_1: uint8x16_t =
-            transmute([12u8, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
-        let rho_east_2: uint8x16_t =
-            transmute([11u8, 8, 9, 10, 15, 12, 13, 14, 3, 0, 1, 2, 7, 4, 5, 6]);
+        let rho_west_1 = vld1q_u8(shuffles::SHIFT_LANE.as_ptr());
+        let rho_east_2 = vld1q_u8(shuffles::XOODOO_RHO_EAST.as_ptr());
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p = veorq_u32(veorq_u32(a, b), c);
             p = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(p), rho_west_1));
-            p = vsliq_n_u32(vshrq_n_u32(p, 32 - 5), p, 5);
-            let mut e = p;
+            let mut e = vsliq_n_u32(vshrq_n_u32(p, 32 - 5), p, 5);
             p = vsliq_n_u32(vshrq_n_u32(p, 32 - 14), p, 14);
             e = veorq_u32(e, p);
             a = veorq_u32(a, e);
             b = veorq_u32(b, e);
             c = veorq_u32(c, e);
+            tracer.record(round, Step::Theta, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // rho west
             b = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(b), rho_west_1));
             c = vsliq_n_u32(vshrq_n_u32(c, 32 - 11), c, 11);
+            tracer.record(round, Step::RhoWest, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // iota
             let round_const = vdupq_n_u32(0);
             let round_const = vsetq_lane_u32(round_key, round_const, 0);
             a = veorq_u32(a, round_const);
+            tracer.record(round, Step::Iota, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // chi
             let a2 = veorq_u32(vbicq_u32(c, b), a);
@@ -86,10 +134,12 @@ fn xoodoo_aarch64(x: &mut [u8; 48]) {
             a = a2;
             b = b2;
             c = c2;
+            tracer.record(round, Step::Chi, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // rho east
             b = vsliq_n_u32(vshrq_n_u32(b, 32 - 1), b, 1);
             c = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(c), rho_east_2));
+            tracer.record(round, Step::RhoEast, &xoodoo_aarch64_lanes([[a, b, c]]));
         }
 
         // Store results back
@@ -102,6 +152,13 @@ fn xoodoo_aarch64(x: &mut [u8; 48]) {
 #[cfg(target_arch = "aarch64")]
 #[inline(never)]
 fn xoodoo_aarch64_x2(x: &mut [u8; 96]) {
+    xoodoo_aarch64_x2_traced(x, &mut NoTrace);
+}
+
+/// `xoodoo_aarch64_x2`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn xoodoo_aarch64_x2_traced(x: &mut [u8; 96], tracer: &mut impl Tracer) {
     use std::arch::aarch64::*;
 
     unsafe {
@@ -112,21 +169,17 @@ fn xoodoo_aarch64_x2(x: &mut [u8; 96]) {
         let mut b1: uint32x4_t = transmute(read128(&x[64..80]));
         let mut c1: uint32x4_t = transmute(read128(&x[80..96]));
 
-        let rho_west_1: uint8x16_t =
-            transmute([12u8, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
-        let rho_east_2: uint8x16_t =
-            transmute([11u8, 8, 9, 10, 15, 12, 13, 14, 3, 0, 1, 2, 7, 4, 5, 6]);
+        let rho_west_1 = vld1q_u8(shuffles::SHIFT_LANE.as_ptr());
+        let rho_east_2 = vld1q_u8(shuffles::XOODOO_RHO_EAST.as_ptr());
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p0 = veorq_u32(veorq_u32(a0, b0), c0);
             let mut p1 = veorq_u32(veorq_u32(a1, b1), c1);
             p0 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(p0), rho_west_1));
             p1 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(p1), rho_west_1));
-            p0 = vsliq_n_u32(vshrq_n_u32(p0, 32 - 5), p0, 5);
-            p1 = vsliq_n_u32(vshrq_n_u32(p1, 32 - 5), p1, 5);
-            let mut e0 = p0;
-            let mut e1 = p1;
+            let mut e0 = vsliq_n_u32(vshrq_n_u32(p0, 32 - 5), p0, 5);
+            let mut e1 = vsliq_n_u32(vshrq_n_u32(p1, 32 - 5), p1, 5);
             p0 = vsliq_n_u32(vshrq_n_u32(p0, 32 - 14), p0, 14);
             p1 = vsliq_n_u32(vshrq_n_u32(p1, 32 - 14), p1, 14);
             e0 = veorq_u32(e0, p0);
@@ -137,18 +190,33 @@ fn xoodoo_aarch64_x2(x: &mut [u8; 96]) {
             b1 = veorq_u32(b1, e1);
             c0 = veorq_u32(c0, e0);
             c1 = veorq_u32(c1, e1);
+            tracer.record(
+                round,
+                Step::Theta,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // rho west
             b0 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(b0), rho_west_1));
             b1 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(b1), rho_west_1));
             c0 = vsliq_n_u32(vshrq_n_u32(c0, 32 - 11), c0, 11);
             c1 = vsliq_n_u32(vshrq_n_u32(c1, 32 - 11), c1, 11);
+            tracer.record(
+                round,
+                Step::RhoWest,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // iota
             let round_const = vdupq_n_u32(0);
             let round_const = vsetq_lane_u32(round_key, round_const, 0);
             a0 = veorq_u32(a0, round_const);
             a1 = veorq_u32(a1, round_const);
+            tracer.record(
+                round,
+                Step::Iota,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // chi
             let t0 = veorq_u32(vbicq_u32(c0, b0), a0);
@@ -163,12 +231,22 @@ fn xoodoo_aarch64_x2(x: &mut [u8; 96]) {
             a1 = t0;
             b1 = t1;
             c1 = t2;
+            tracer.record(
+                round,
+                Step::Chi,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // rho east
             b0 = vsliq_n_u32(vshrq_n_u32(b0, 32 - 1), b0, 1);
             b1 = vsliq_n_u32(vshrq_n_u32(b1, 32 - 1), b1, 1);
             c0 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(c0), rho_east_2));
             c1 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(c1), rho_east_2));
+            tracer.record(
+                round,
+                Step::RhoEast,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
         }
 
         // Store results back
@@ -184,6 +262,13 @@ fn xoodoo_aarch64_x2(x: &mut [u8; 96]) {
 #[cfg(target_arch = "aarch64")]
 #[inline(never)]
 fn xoodoo_aarch64_x4(state: &mut [u8; 192]) {
+    xoodoo_aarch64_x4_traced(state, &mut NoTrace);
+}
+
+/// `xoodoo_aarch64_x4`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn xoodoo_aarch64_x4_traced(state: &mut [u8; 192], tracer: &mut impl Tracer) {
     use std::arch::aarch64::*;
 
     unsafe {
@@ -217,23 +302,22 @@ fn xoodoo_aarch64_x4(state: &mut [u8; 192]) {
         ];
         x = [
             vtrn1q_u32(x[0], x[3]),
-            vtrn1q_u32(x[1], x[4]),
-            vtrn1q_u32(x[2], x[5]),
             vtrn2q_u32(x[0], x[3]),
-            vtrn2q_u32(x[1], x[4]),
-            vtrn2q_u32(x[2], x[5]),
             vtrn1q_u32(x[6], x[9]),
-            vtrn1q_u32(x[7], x[10]),
-            vtrn1q_u32(x[8], x[11]),
             vtrn2q_u32(x[6], x[9]),
+            vtrn1q_u32(x[1], x[4]),
+            vtrn2q_u32(x[1], x[4]),
+            vtrn1q_u32(x[7], x[10]),
             vtrn2q_u32(x[7], x[10]),
+            vtrn1q_u32(x[2], x[5]),
+            vtrn2q_u32(x[2], x[5]),
+            vtrn1q_u32(x[8], x[11]),
             vtrn2q_u32(x[8], x[11]),
         ];
 
-        let rho_east_2: uint8x16_t =
-            transmute([3u8, 0, 1, 2, 7, 4, 5, 6, 11, 8, 9, 10, 15, 12, 13, 14]);
+        let rho_east_2 = vld1q_u8(shuffles::ROTATE_WORDS_8.as_ptr());
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p0 = veorq_u32(x[0], veorq_u32(x[4], x[8]));
             let mut p1 = veorq_u32(x[1], veorq_u32(x[5], x[9]));
@@ -251,7 +335,7 @@ fn xoodoo_aarch64_x4(state: &mut [u8; 192]) {
             e1 = veorq_u32(e1, p1);
             e2 = veorq_u32(e2, p2);
             e3 = veorq_u32(e3, p3);
-            (e0, e1, e2, e3) = (e1, e2, e3, e0);
+            (e0, e1, e2, e3) = (e3, e0, e1, e2);
             x[0] = veorq_u32(x[0], e0);
             x[4] = veorq_u32(x[4], e0);
             x[8] = veorq_u32(x[8], e0);
@@ -264,6 +348,11 @@ fn xoodoo_aarch64_x4(state: &mut [u8; 192]) {
             x[3] = veorq_u32(x[3], e3);
             x[7] = veorq_u32(x[7], e3);
             x[11] = veorq_u32(x[11], e3);
+            tracer.record(
+                round,
+                Step::Theta,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // rho west
             (x[4], x[5], x[6], x[7]) = (x[7], x[4], x[5], x[6]);
@@ -271,36 +360,51 @@ fn xoodoo_aarch64_x4(state: &mut [u8; 192]) {
             x[9] = vsliq_n_u32(vshrq_n_u32(x[9], 32 - 11), x[9], 11);
             x[10] = vsliq_n_u32(vshrq_n_u32(x[10], 32 - 11), x[10], 11);
             x[11] = vsliq_n_u32(vshrq_n_u32(x[11], 32 - 11), x[11], 11);
+            tracer.record(
+                round,
+                Step::RhoWest,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // iota
             let round_key = vdupq_n_u32(round_key);
             x[0] = veorq_u32(x[0], round_key);
+            tracer.record(
+                round,
+                Step::Iota,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // chi
-            let t0 = veorq_u32(vbicq_u32(x[0], x[4]), x[8]);
-            let t1 = veorq_u32(vbicq_u32(x[4], x[8]), x[0]);
-            let t2 = veorq_u32(vbicq_u32(x[8], x[0]), x[4]);
+            let t0 = veorq_u32(vbicq_u32(x[8], x[4]), x[0]);
+            let t1 = veorq_u32(vbicq_u32(x[0], x[8]), x[4]);
+            let t2 = veorq_u32(vbicq_u32(x[4], x[0]), x[8]);
             x[0] = t0;
             x[4] = t1;
             x[8] = t2;
-            let t0 = veorq_u32(vbicq_u32(x[1], x[5]), x[9]);
-            let t1 = veorq_u32(vbicq_u32(x[5], x[9]), x[1]);
-            let t2 = veorq_u32(vbicq_u32(x[9], x[1]), x[5]);
+            let t0 = veorq_u32(vbicq_u32(x[9], x[5]), x[1]);
+            let t1 = veorq_u32(vbicq_u32(x[1], x[9]), x[5]);
+            let t2 = veorq_u32(vbicq_u32(x[5], x[1]), x[9]);
             x[1] = t0;
             x[5] = t1;
             x[9] = t2;
-            let t0 = veorq_u32(vbicq_u32(x[2], x[6]), x[10]);
-            let t1 = veorq_u32(vbicq_u32(x[6], x[10]), x[2]);
-            let t2 = veorq_u32(vbicq_u32(x[10], x[2]), x[6]);
+            let t0 = veorq_u32(vbicq_u32(x[10], x[6]), x[2]);
+            let t1 = veorq_u32(vbicq_u32(x[2], x[10]), x[6]);
+            let t2 = veorq_u32(vbicq_u32(x[6], x[2]), x[10]);
             x[2] = t0;
             x[6] = t1;
             x[10] = t2;
-            let t0 = veorq_u32(vbicq_u32(x[3], x[7]), x[11]);
-            let t1 = veorq_u32(vbicq_u32(x[7], x[11]), x[3]);
-            let t2 = veorq_u32(vbicq_u32(x[11], x[3]), x[7]);
+            let t0 = veorq_u32(vbicq_u32(x[11], x[7]), x[3]);
+            let t1 = veorq_u32(vbicq_u32(x[3], x[11]), x[7]);
+            let t2 = veorq_u32(vbicq_u32(x[7], x[3]), x[11]);
             x[3] = t0;
             x[7] = t1;
             x[11] = t2;
+            tracer.record(
+                round,
+                Step::Chi,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // rho east
             x[4] = vsliq_n_u32(vshrq_n_u32(x[4], 32 - 1), x[4], 1);
@@ -312,21 +416,26 @@ fn xoodoo_aarch64_x4(state: &mut [u8; 192]) {
             x[9] = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(x[9]), rho_east_2));
             x[10] = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(x[10]), rho_east_2));
             x[11] = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(x[11]), rho_east_2));
+            tracer.record(
+                round,
+                Step::RhoEast,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
         }
 
         let mut x: [uint64x2_t; 12] = [
-            transmute(vtrn1q_u32(x[0], x[3])),
-            transmute(vtrn1q_u32(x[1], x[4])),
-            transmute(vtrn1q_u32(x[2], x[5])),
-            transmute(vtrn2q_u32(x[0], x[3])),
-            transmute(vtrn2q_u32(x[1], x[4])),
-            transmute(vtrn2q_u32(x[2], x[5])),
-            transmute(vtrn1q_u32(x[6], x[9])),
-            transmute(vtrn1q_u32(x[7], x[10])),
-            transmute(vtrn1q_u32(x[8], x[11])),
-            transmute(vtrn2q_u32(x[6], x[9])),
-            transmute(vtrn2q_u32(x[7], x[10])),
-            transmute(vtrn2q_u32(x[8], x[11])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[0], x[1])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[4], x[5])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[8], x[9])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[0], x[1])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[4], x[5])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[8], x[9])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[2], x[3])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[6], x[7])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[10], x[11])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[2], x[3])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[6], x[7])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[10], x[11])),
         ];
         x = [
             vtrn1q_u64(x[0], x[6]),
@@ -363,6 +472,14 @@ fn xoodoo_aarch64_x4(state: &mut [u8; 192]) {
 #[target_feature(enable = "sha3")]
 #[inline(never)]
 unsafe fn xoodoo_aarch64_sha3(x: &mut [u8; 48]) {
+    unsafe { xoodoo_aarch64_sha3_traced(x, &mut NoTrace) }
+}
+
+/// `xoodoo_aarch64_sha3`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline]
+unsafe fn xoodoo_aarch64_sha3_traced(x: &mut [u8; 48], tracer: &mut impl Tracer) {
     use std::arch::aarch64::*;
 
     unsafe {
@@ -370,43 +487,45 @@ unsafe fn xoodoo_aarch64_sha3(x: &mut [u8; 48]) {
         let mut b: uint32x4_t = transmute(read128(&x[16..32]));
         let mut c: uint32x4_t = transmute(read128(&x[32..48]));
 
-        let rho_west_1: uint8x16_t =
-            transmute([12u8, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
-        let rho_east_2: uint8x16_t =
-            transmute([11u8, 8, 9, 10, 15, 12, 13, 14, 3, 0, 1, 2, 7, 4, 5, 6]);
+        let rho_west_1 = vld1q_u8(shuffles::SHIFT_LANE.as_ptr());
+        let rho_east_2 = vld1q_u8(shuffles::XOODOO_RHO_EAST.as_ptr());
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p = veor3q_u32(a, b, c);
             p = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(p), rho_west_1));
-            p = vsliq_n_u32(vshrq_n_u32(p, 32 - 5), p, 5);
-            let mut e = p;
+            let mut e = vsliq_n_u32(vshrq_n_u32(p, 32 - 5), p, 5);
             p = vsliq_n_u32(vshrq_n_u32(p, 32 - 14), p, 14);
             e = veorq_u32(e, p);
             a = veorq_u32(a, e);
             b = veorq_u32(b, e);
             c = veorq_u32(c, e);
+            tracer.record(round, Step::Theta, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // rho west
             b = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(b), rho_west_1));
             c = vsliq_n_u32(vshrq_n_u32(c, 32 - 11), c, 11);
+            tracer.record(round, Step::RhoWest, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // iota
             let round_const = vdupq_n_u32(0);
             let round_const = vsetq_lane_u32(round_key, round_const, 0);
             a = veorq_u32(a, round_const);
+            tracer.record(round, Step::Iota, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // chi
-            let a2 = vbcaxq_u32(c, b, a);
-            let b2 = vbcaxq_u32(a, c, b);
-            let c2 = vbcaxq_u32(b, a, c);
+            let a2 = vbcaxq_u32(a, c, b);
+            let b2 = vbcaxq_u32(b, a, c);
+            let c2 = vbcaxq_u32(c, b, a);
             a = a2;
             b = b2;
             c = c2;
+            tracer.record(round, Step::Chi, &xoodoo_aarch64_lanes([[a, b, c]]));
 
             // rho east
             b = vsliq_n_u32(vshrq_n_u32(b, 32 - 1), b, 1);
             c = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(c), rho_east_2));
+            tracer.record(round, Step::RhoEast, &xoodoo_aarch64_lanes([[a, b, c]]));
         }
 
         // Store results back
@@ -420,6 +539,14 @@ unsafe fn xoodoo_aarch64_sha3(x: &mut [u8; 48]) {
 #[target_feature(enable = "sha3")]
 #[inline(never)]
 unsafe fn xoodoo_aarch64_sha3_x2(x: &mut [u8; 96]) {
+    unsafe { xoodoo_aarch64_sha3_x2_traced(x, &mut NoTrace) }
+}
+
+/// `xoodoo_aarch64_sha3_x2`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline]
+unsafe fn xoodoo_aarch64_sha3_x2_traced(x: &mut [u8; 96], tracer: &mut impl Tracer) {
     use std::arch::aarch64::*;
 
     unsafe {
@@ -430,21 +557,17 @@ unsafe fn xoodoo_aarch64_sha3_x2(x: &mut [u8; 96]) {
         let mut b1: uint32x4_t = transmute(read128(&x[64..80]));
         let mut c1: uint32x4_t = transmute(read128(&x[80..96]));
 
-        let rho_west_1: uint8x16_t =
-            transmute([12u8, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
-        let rho_east_2: uint8x16_t =
-            transmute([11u8, 8, 9, 10, 15, 12, 13, 14, 3, 0, 1, 2, 7, 4, 5, 6]);
+        let rho_west_1 = vld1q_u8(shuffles::SHIFT_LANE.as_ptr());
+        let rho_east_2 = vld1q_u8(shuffles::XOODOO_RHO_EAST.as_ptr());
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p0 = veor3q_u32(a0, b0, c0);
             let mut p1 = veor3q_u32(a1, b1, c1);
             p0 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(p0), rho_west_1));
             p1 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(p1), rho_west_1));
-            p0 = vsliq_n_u32(vshrq_n_u32(p0, 32 - 5), p0, 5);
-            p1 = vsliq_n_u32(vshrq_n_u32(p1, 32 - 5), p1, 5);
-            let mut e0 = p0;
-            let mut e1 = p1;
+            let mut e0 = vsliq_n_u32(vshrq_n_u32(p0, 32 - 5), p0, 5);
+            let mut e1 = vsliq_n_u32(vshrq_n_u32(p1, 32 - 5), p1, 5);
             p0 = vsliq_n_u32(vshrq_n_u32(p0, 32 - 14), p0, 14);
             p1 = vsliq_n_u32(vshrq_n_u32(p1, 32 - 14), p1, 14);
             e0 = veorq_u32(e0, p0);
@@ -455,38 +578,63 @@ unsafe fn xoodoo_aarch64_sha3_x2(x: &mut [u8; 96]) {
             b1 = veorq_u32(b1, e1);
             c0 = veorq_u32(c0, e0);
             c1 = veorq_u32(c1, e1);
+            tracer.record(
+                round,
+                Step::Theta,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // rho west
             b0 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(b0), rho_west_1));
             b1 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(b1), rho_west_1));
             c0 = vsliq_n_u32(vshrq_n_u32(c0, 32 - 11), c0, 11);
             c1 = vsliq_n_u32(vshrq_n_u32(c1, 32 - 11), c1, 11);
+            tracer.record(
+                round,
+                Step::RhoWest,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // iota
             let round_const = vdupq_n_u32(0);
             let round_const = vsetq_lane_u32(round_key, round_const, 0);
             a0 = veorq_u32(a0, round_const);
             a1 = veorq_u32(a1, round_const);
+            tracer.record(
+                round,
+                Step::Iota,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // chi
-            let t0 = vbcaxq_u32(c0, b0, a0);
-            let t1 = vbcaxq_u32(a0, c0, b0);
-            let t2 = vbcaxq_u32(b0, a0, c0);
+            let t0 = vbcaxq_u32(a0, c0, b0);
+            let t1 = vbcaxq_u32(b0, a0, c0);
+            let t2 = vbcaxq_u32(c0, b0, a0);
             a0 = t0;
             b0 = t1;
             c0 = t2;
-            let t0 = vbcaxq_u32(c1, b1, a1);
-            let t1 = vbcaxq_u32(a1, c1, b1);
-            let t2 = vbcaxq_u32(b1, a1, c1);
+            let t0 = vbcaxq_u32(a1, c1, b1);
+            let t1 = vbcaxq_u32(b1, a1, c1);
+            let t2 = vbcaxq_u32(c1, b1, a1);
             a1 = t0;
             b1 = t1;
             c1 = t2;
+            tracer.record(
+                round,
+                Step::Chi,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
 
             // rho east
             b0 = vsliq_n_u32(vshrq_n_u32(b0, 32 - 1), b0, 1);
             b1 = vsliq_n_u32(vshrq_n_u32(b1, 32 - 1), b1, 1);
             c0 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(c0), rho_east_2));
             c1 = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(c1), rho_east_2));
+            tracer.record(
+                round,
+                Step::RhoEast,
+                &xoodoo_aarch64_lanes([[a0, b0, c0], [a1, b1, c1]]),
+            );
         }
 
         // Store results back
@@ -503,6 +651,14 @@ unsafe fn xoodoo_aarch64_sha3_x2(x: &mut [u8; 96]) {
 #[target_feature(enable = "sha3")]
 #[inline(never)]
 unsafe fn xoodoo_aarch64_sha3_x4(state: &mut [u8; 192]) {
+    unsafe { xoodoo_aarch64_sha3_x4_traced(state, &mut NoTrace) }
+}
+
+/// `xoodoo_aarch64_sha3_x4`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline]
+unsafe fn xoodoo_aarch64_sha3_x4_traced(state: &mut [u8; 192], tracer: &mut impl Tracer) {
     use std::arch::aarch64::*;
 
     unsafe {
@@ -536,23 +692,22 @@ unsafe fn xoodoo_aarch64_sha3_x4(state: &mut [u8; 192]) {
         ];
         x = [
             vtrn1q_u32(x[0], x[3]),
-            vtrn1q_u32(x[1], x[4]),
-            vtrn1q_u32(x[2], x[5]),
             vtrn2q_u32(x[0], x[3]),
-            vtrn2q_u32(x[1], x[4]),
-            vtrn2q_u32(x[2], x[5]),
             vtrn1q_u32(x[6], x[9]),
-            vtrn1q_u32(x[7], x[10]),
-            vtrn1q_u32(x[8], x[11]),
             vtrn2q_u32(x[6], x[9]),
+            vtrn1q_u32(x[1], x[4]),
+            vtrn2q_u32(x[1], x[4]),
+            vtrn1q_u32(x[7], x[10]),
             vtrn2q_u32(x[7], x[10]),
+            vtrn1q_u32(x[2], x[5]),
+            vtrn2q_u32(x[2], x[5]),
+            vtrn1q_u32(x[8], x[11]),
             vtrn2q_u32(x[8], x[11]),
         ];
 
-        let rho_east_2: uint8x16_t =
-            transmute([3u8, 0, 1, 2, 7, 4, 5, 6, 11, 8, 9, 10, 15, 12, 13, 14]);
+        let rho_east_2 = vld1q_u8(shuffles::ROTATE_WORDS_8.as_ptr());
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p0 = veor3q_u32(x[0], x[4], x[8]);
             let mut p1 = veor3q_u32(x[1], x[5], x[9]);
@@ -570,7 +725,7 @@ unsafe fn xoodoo_aarch64_sha3_x4(state: &mut [u8; 192]) {
             e1 = veorq_u32(e1, p1);
             e2 = veorq_u32(e2, p2);
             e3 = veorq_u32(e3, p3);
-            (e0, e1, e2, e3) = (e1, e2, e3, e0);
+            (e0, e1, e2, e3) = (e3, e0, e1, e2);
             x[0] = veorq_u32(x[0], e0);
             x[4] = veorq_u32(x[4], e0);
             x[8] = veorq_u32(x[8], e0);
@@ -583,6 +738,11 @@ unsafe fn xoodoo_aarch64_sha3_x4(state: &mut [u8; 192]) {
             x[3] = veorq_u32(x[3], e3);
             x[7] = veorq_u32(x[7], e3);
             x[11] = veorq_u32(x[11], e3);
+            tracer.record(
+                round,
+                Step::Theta,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // rho west
             (x[4], x[5], x[6], x[7]) = (x[7], x[4], x[5], x[6]);
@@ -590,36 +750,51 @@ unsafe fn xoodoo_aarch64_sha3_x4(state: &mut [u8; 192]) {
             x[9] = vsliq_n_u32(vshrq_n_u32(x[9], 32 - 11), x[9], 11);
             x[10] = vsliq_n_u32(vshrq_n_u32(x[10], 32 - 11), x[10], 11);
             x[11] = vsliq_n_u32(vshrq_n_u32(x[11], 32 - 11), x[11], 11);
+            tracer.record(
+                round,
+                Step::RhoWest,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // iota
             let round_key = vdupq_n_u32(round_key);
             x[0] = veorq_u32(x[0], round_key);
+            tracer.record(
+                round,
+                Step::Iota,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // chi
-            let t0 = vbcaxq_u32(x[0], x[4], x[8]);
-            let t1 = vbcaxq_u32(x[4], x[8], x[0]);
-            let t2 = vbcaxq_u32(x[8], x[0], x[4]);
+            let t0 = vbcaxq_u32(x[0], x[8], x[4]);
+            let t1 = vbcaxq_u32(x[4], x[0], x[8]);
+            let t2 = vbcaxq_u32(x[8], x[4], x[0]);
             x[0] = t0;
             x[4] = t1;
             x[8] = t2;
-            let t0 = vbcaxq_u32(x[1], x[5], x[9]);
-            let t1 = vbcaxq_u32(x[5], x[9], x[1]);
-            let t2 = vbcaxq_u32(x[9], x[1], x[5]);
+            let t0 = vbcaxq_u32(x[1], x[9], x[5]);
+            let t1 = vbcaxq_u32(x[5], x[1], x[9]);
+            let t2 = vbcaxq_u32(x[9], x[5], x[1]);
             x[1] = t0;
             x[5] = t1;
             x[9] = t2;
-            let t0 = vbcaxq_u32(x[2], x[6], x[10]);
-            let t1 = vbcaxq_u32(x[6], x[10], x[2]);
-            let t2 = vbcaxq_u32(x[10], x[2], x[6]);
+            let t0 = vbcaxq_u32(x[2], x[10], x[6]);
+            let t1 = vbcaxq_u32(x[6], x[2], x[10]);
+            let t2 = vbcaxq_u32(x[10], x[6], x[2]);
             x[2] = t0;
             x[6] = t1;
             x[10] = t2;
-            let t0 = vbcaxq_u32(x[3], x[7], x[11]);
-            let t1 = vbcaxq_u32(x[7], x[11], x[3]);
-            let t2 = vbcaxq_u32(x[11], x[3], x[7]);
+            let t0 = vbcaxq_u32(x[3], x[11], x[7]);
+            let t1 = vbcaxq_u32(x[7], x[3], x[11]);
+            let t2 = vbcaxq_u32(x[11], x[7], x[3]);
             x[3] = t0;
             x[7] = t1;
             x[11] = t2;
+            tracer.record(
+                round,
+                Step::Chi,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
 
             // rho east
             x[4] = vsliq_n_u32(vshrq_n_u32(x[4], 32 - 1), x[4], 1);
@@ -631,21 +806,26 @@ unsafe fn xoodoo_aarch64_sha3_x4(state: &mut [u8; 192]) {
             x[9] = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(x[9]), rho_east_2));
             x[10] = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(x[10]), rho_east_2));
             x[11] = vreinterpretq_u32_u8(vqtbl1q_u8(vreinterpretq_u8_u32(x[11]), rho_east_2));
+            tracer.record(
+                round,
+                Step::RhoEast,
+                &transmute::<[uint32x4_t; 12], [[u32; 4]; 12]>(x),
+            );
         }
 
         let mut x: [uint64x2_t; 12] = [
-            transmute(vtrn1q_u32(x[0], x[3])),
-            transmute(vtrn1q_u32(x[1], x[4])),
-            transmute(vtrn1q_u32(x[2], x[5])),
-            transmute(vtrn2q_u32(x[0], x[3])),
-            transmute(vtrn2q_u32(x[1], x[4])),
-            transmute(vtrn2q_u32(x[2], x[5])),
-            transmute(vtrn1q_u32(x[6], x[9])),
-            transmute(vtrn1q_u32(x[7], x[10])),
-            transmute(vtrn1q_u32(x[8], x[11])),
-            transmute(vtrn2q_u32(x[6], x[9])),
-            transmute(vtrn2q_u32(x[7], x[10])),
-            transmute(vtrn2q_u32(x[8], x[11])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[0], x[1])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[4], x[5])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[8], x[9])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[0], x[1])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[4], x[5])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[8], x[9])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[2], x[3])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[6], x[7])),
+            vreinterpretq_u64_u32(vtrn1q_u32(x[10], x[11])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[2], x[3])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[6], x[7])),
+            vreinterpretq_u64_u32(vtrn2q_u32(x[10], x[11])),
         ];
         x = [
             vtrn1q_u64(x[0], x[6]),
@@ -680,1021 +860,216 @@ unsafe fn xoodoo_aarch64_sha3_x4(state: &mut [u8; 192]) {
 
 #[inline(never)]
 fn xoodoo_scalar(state: &mut [u8; 48]) {
-    let mut x = [
-        read32(&state[0..4]),
-        read32(&state[4..8]),
-        read32(&state[8..12]),
-        read32(&state[12..16]),
-        read32(&state[16..20]),
-        read32(&state[20..24]),
-        read32(&state[24..28]),
-        read32(&state[28..32]),
-        read32(&state[32..36]),
-        read32(&state[36..40]),
-        read32(&state[40..44]),
-        read32(&state[44..48]),
-    ];
-
-    for &round_key in &ROUND_KEYS {
-        // theta
-        let mut p0 = x[0] ^ x[4] ^ x[8];
-        let mut p1 = x[1] ^ x[5] ^ x[9];
-        let mut p2 = x[2] ^ x[6] ^ x[10];
-        let mut p3 = x[3] ^ x[7] ^ x[11];
-        p0 = p0.rotate_left(5) ^ p0.rotate_left(14);
-        p1 = p1.rotate_left(5) ^ p1.rotate_left(14);
-        p2 = p2.rotate_left(5) ^ p2.rotate_left(14);
-        p3 = p3.rotate_left(5) ^ p3.rotate_left(14);
-        (p0, p1, p2, p3) = (p1, p2, p3, p0);
-        x[0] ^= p0;
-        x[4] ^= p0;
-        x[8] ^= p0;
-        x[1] ^= p1;
-        x[5] ^= p1;
-        x[9] ^= p1;
-        x[2] ^= p2;
-        x[6] ^= p2;
-        x[10] ^= p2;
-        x[3] ^= p3;
-        x[7] ^= p3;
-        x[11] ^= p3;
-
-        // rho west
-        (x[4], x[5], x[6], x[7]) = (x[7], x[4], x[5], x[6]);
-        x[8] = x[8].rotate_left(11);
-        x[9] = x[9].rotate_left(11);
-        x[10] = x[10].rotate_left(11);
-        x[11] = x[11].rotate_left(11);
-
-        // iota
-        x[0] ^= round_key;
-
-        // chi
-        let t0 = x[0] ^ (!x[4] & x[8]);
-        let t1 = x[4] ^ (!x[8] & x[0]);
-        let t2 = x[8] ^ (!x[0] & x[4]);
-        x[0] = t0;
-        x[4] = t1;
-        x[8] = t2;
-        let t0 = x[1] ^ (!x[5] & x[9]);
-        let t1 = x[5] ^ (!x[9] & x[1]);
-        let t2 = x[9] ^ (!x[1] & x[5]);
-        x[1] = t0;
-        x[5] = t1;
-        x[9] = t2;
-        let t0 = x[2] ^ (!x[6] & x[10]);
-        let t1 = x[6] ^ (!x[10] & x[2]);
-        let t2 = x[10] ^ (!x[2] & x[6]);
-        x[2] = t0;
-        x[6] = t1;
-        x[10] = t2;
-        let t0 = x[3] ^ (!x[7] & x[11]);
-        let t1 = x[7] ^ (!x[11] & x[3]);
-        let t2 = x[11] ^ (!x[3] & x[7]);
-        x[3] = t0;
-        x[7] = t1;
-        x[11] = t2;
-
-        // rho east
-        x[4] = x[4].rotate_left(1);
-        x[5] = x[5].rotate_left(1);
-        x[6] = x[6].rotate_left(1);
-        x[7] = x[7].rotate_left(1);
-        (x[8], x[9], x[10], x[11]) = (x[10], x[11], x[8], x[9]);
-        x[8] = x[8].rotate_left(8);
-        x[9] = x[9].rotate_left(8);
-        x[10] = x[10].rotate_left(8);
-        x[11] = x[11].rotate_left(8);
-    }
+    xoodoo_scalar_rounds(state, &ROUND_KEYS);
+}
 
-    write32(x[0], &mut state[0..4]);
-    write32(x[1], &mut state[4..8]);
-    write32(x[2], &mut state[8..12]);
-    write32(x[3], &mut state[12..16]);
-    write32(x[4], &mut state[16..20]);
-    write32(x[5], &mut state[20..24]);
-    write32(x[6], &mut state[24..28]);
-    write32(x[7], &mut state[28..32]);
-    write32(x[8], &mut state[32..36]);
-    write32(x[9], &mut state[36..40]);
-    write32(x[10], &mut state[40..44]);
-    write32(x[11], &mut state[44..48]);
+/// `xoodoo_scalar` with the given round constants, one round per constant.
+#[inline(always)]
+fn xoodoo_scalar_rounds(state: &mut [u8; 48], round_keys: &[u32]) {
+    scalar::xoodoo_scalar_traced(std::array::from_mut(state), round_keys, &mut NoTrace);
 }
 
+/// `N` interleaved instances of `xoodoo_scalar`.
 #[inline(never)]
-fn xoodoo_scalar_x2(state: &mut [u8; 96]) {
-    let mut x = [
-        read32(&state[0..4]),
-        read32(&state[4..8]),
-        read32(&state[8..12]),
-        read32(&state[12..16]),
-        read32(&state[16..20]),
-        read32(&state[20..24]),
-        read32(&state[24..28]),
-        read32(&state[28..32]),
-        read32(&state[32..36]),
-        read32(&state[36..40]),
-        read32(&state[40..44]),
-        read32(&state[44..48]),
-        read32(&state[48..52]),
-        read32(&state[52..56]),
-        read32(&state[56..60]),
-        read32(&state[60..64]),
-        read32(&state[64..68]),
-        read32(&state[68..72]),
-        read32(&state[72..76]),
-        read32(&state[76..80]),
-        read32(&state[80..84]),
-        read32(&state[84..88]),
-        read32(&state[88..92]),
-        read32(&state[92..96]),
-    ];
-
-    for &round_key in &ROUND_KEYS {
-        // theta
-        let mut p0a = x[0] ^ x[4] ^ x[8];
-        let mut p1a = x[1] ^ x[5] ^ x[9];
-        let mut p2a = x[2] ^ x[6] ^ x[10];
-        let mut p3a = x[3] ^ x[7] ^ x[11];
-        let mut p0b = x[12] ^ x[16] ^ x[20];
-        let mut p1b = x[13] ^ x[17] ^ x[21];
-        let mut p2b = x[14] ^ x[18] ^ x[22];
-        let mut p3b = x[15] ^ x[19] ^ x[23];
-        p0a = p0a.rotate_left(5) ^ p0a.rotate_left(14);
-        p1a = p1a.rotate_left(5) ^ p1a.rotate_left(14);
-        p2a = p2a.rotate_left(5) ^ p2a.rotate_left(14);
-        p3a = p3a.rotate_left(5) ^ p3a.rotate_left(14);
-        p0b = p0b.rotate_left(5) ^ p0b.rotate_left(14);
-        p1b = p1b.rotate_left(5) ^ p1b.rotate_left(14);
-        p2b = p2b.rotate_left(5) ^ p2b.rotate_left(14);
-        p3b = p3b.rotate_left(5) ^ p3b.rotate_left(14);
-        (p0a, p1a, p2a, p3a) = (p1a, p2a, p3a, p0a);
-        (p0b, p1b, p2b, p3b) = (p1b, p2b, p3b, p0b);
-        x[0] ^= p0a;
-        x[4] ^= p0a;
-        x[8] ^= p0a;
-        x[1] ^= p1a;
-        x[5] ^= p1a;
-        x[9] ^= p1a;
-        x[2] ^= p2a;
-        x[6] ^= p2a;
-        x[10] ^= p2a;
-        x[3] ^= p3a;
-        x[7] ^= p3a;
-        x[11] ^= p3a;
-        x[12] ^= p0b;
-        x[16] ^= p0b;
-        x[20] ^= p0b;
-        x[13] ^= p1b;
-        x[17] ^= p1b;
-        x[21] ^= p1b;
-        x[14] ^= p2b;
-        x[18] ^= p2b;
-        x[22] ^= p2b;
-        x[15] ^= p3b;
-        x[19] ^= p3b;
-        x[23] ^= p3b;
-
-        // rho west
-        (x[4], x[5], x[6], x[7]) = (x[7], x[4], x[5], x[6]);
-        (x[16], x[17], x[18], x[19]) = (x[19], x[16], x[17], x[18]);
-        x[8] = x[8].rotate_left(11);
-        x[9] = x[9].rotate_left(11);
-        x[10] = x[10].rotate_left(11);
-        x[11] = x[11].rotate_left(11);
-        x[20] = x[20].rotate_left(11);
-        x[21] = x[21].rotate_left(11);
-        x[22] = x[22].rotate_left(11);
-        x[23] = x[23].rotate_left(11);
-
-        // iota
-        x[0] ^= round_key;
-        x[12] ^= round_key;
-
-        // chi
-        let t0 = x[0] ^ (!x[4] & x[8]);
-        let t1 = x[4] ^ (!x[8] & x[0]);
-        let t2 = x[8] ^ (!x[0] & x[4]);
-        x[0] = t0;
-        x[4] = t1;
-        x[8] = t2;
-        let t0 = x[1] ^ (!x[5] & x[9]);
-        let t1 = x[5] ^ (!x[9] & x[1]);
-        let t2 = x[9] ^ (!x[1] & x[5]);
-        x[1] = t0;
-        x[5] = t1;
-        x[9] = t2;
-        let t0 = x[2] ^ (!x[6] & x[10]);
-        let t1 = x[6] ^ (!x[10] & x[2]);
-        let t2 = x[10] ^ (!x[2] & x[6]);
-        x[2] = t0;
-        x[6] = t1;
-        x[10] = t2;
-        let t0 = x[3] ^ (!x[7] & x[11]);
-        let t1 = x[7] ^ (!x[11] & x[3]);
-        let t2 = x[11] ^ (!x[3] & x[7]);
-        x[3] = t0;
-        x[7] = t1;
-        x[11] = t2;
-        let t0 = x[12] ^ (!x[16] & x[20]);
-        let t1 = x[16] ^ (!x[20] & x[12]);
-        let t2 = x[20] ^ (!x[12] & x[16]);
-        x[12] = t0;
-        x[16] = t1;
-        x[20] = t2;
-        let t0 = x[13] ^ (!x[17] & x[21]);
-        let t1 = x[17] ^ (!x[21] & x[13]);
-        let t2 = x[21] ^ (!x[13] & x[17]);
-        x[13] = t0;
-        x[17] = t1;
-        x[21] = t2;
-        let t0 = x[14] ^ (!x[18] & x[22]);
-        let t1 = x[18] ^ (!x[22] & x[14]);
-        let t2 = x[22] ^ (!x[14] & x[18]);
-        x[14] = t0;
-        x[18] = t1;
-        x[22] = t2;
-        let t0 = x[15] ^ (!x[19] & x[23]);
-        let t1 = x[19] ^ (!x[23] & x[15]);
-        let t2 = x[23] ^ (!x[15] & x[19]);
-        x[15] = t0;
-        x[19] = t1;
-        x[23] = t2;
-
-        // rho east
-        x[4] = x[4].rotate_left(1);
-        x[5] = x[5].rotate_left(1);
-        x[6] = x[6].rotate_left(1);
-        x[7] = x[7].rotate_left(1);
-        (x[8], x[9], x[10], x[11]) = (x[10], x[11], x[8], x[9]);
-        x[8] = x[8].rotate_left(8);
-        x[9] = x[9].rotate_left(8);
-        x[10] = x[10].rotate_left(8);
-        x[11] = x[11].rotate_left(8);
-        x[16] = x[16].rotate_left(1);
-        x[17] = x[17].rotate_left(1);
-        x[18] = x[18].rotate_left(1);
-        x[19] = x[19].rotate_left(1);
-        (x[20], x[21], x[22], x[23]) = (x[22], x[23], x[20], x[21]);
-        x[20] = x[20].rotate_left(8);
-        x[21] = x[21].rotate_left(8);
-        x[22] = x[22].rotate_left(8);
-        x[23] = x[23].rotate_left(8);
-    }
+fn xoodoo_scalar_xn<const N: usize>(states: &mut [[u8; 48]; N]) {
+    scalar::xoodoo_scalar_traced(states, &ROUND_KEYS, &mut NoTrace);
+}
 
-    write32(x[0], &mut state[0..4]);
-    write32(x[1], &mut state[4..8]);
-    write32(x[2], &mut state[8..12]);
-    write32(x[3], &mut state[12..16]);
-    write32(x[4], &mut state[16..20]);
-    write32(x[5], &mut state[20..24]);
-    write32(x[6], &mut state[24..28]);
-    write32(x[7], &mut state[28..32]);
-    write32(x[8], &mut state[32..36]);
-    write32(x[9], &mut state[36..40]);
-    write32(x[10], &mut state[40..44]);
-    write32(x[11], &mut state[44..48]);
-    write32(x[12], &mut state[48..52]);
-    write32(x[13], &mut state[52..56]);
-    write32(x[14], &mut state[56..60]);
-    write32(x[15], &mut state[60..64]);
-    write32(x[16], &mut state[64..68]);
-    write32(x[17], &mut state[68..72]);
-    write32(x[18], &mut state[72..76]);
-    write32(x[19], &mut state[76..80]);
-    write32(x[20], &mut state[80..84]);
-    write32(x[21], &mut state[84..88]);
-    write32(x[22], &mut state[88..92]);
-    write32(x[23], &mut state[92..96]);
+#[inline(never)]
+fn xoodoo_scalar_x2(state: &mut [u8; 96]) {
+    scalar::xoodoo_scalar_traced(as_states::<2>(state), &ROUND_KEYS, &mut NoTrace);
 }
 
 #[inline(never)]
 fn xoodoo_scalar_x4(state: &mut [u8; 192]) {
-    let mut x = [
-        read32(&state[0..4]),
-        read32(&state[4..8]),
-        read32(&state[8..12]),
-        read32(&state[12..16]),
-        read32(&state[16..20]),
-        read32(&state[20..24]),
-        read32(&state[24..28]),
-        read32(&state[28..32]),
-        read32(&state[32..36]),
-        read32(&state[36..40]),
-        read32(&state[40..44]),
-        read32(&state[44..48]),
-        read32(&state[48..52]),
-        read32(&state[52..56]),
-        read32(&state[56..60]),
-        read32(&state[60..64]),
-        read32(&state[64..68]),
-        read32(&state[68..72]),
-        read32(&state[72..76]),
-        read32(&state[76..80]),
-        read32(&state[80..84]),
-        read32(&state[84..88]),
-        read32(&state[88..92]),
-        read32(&state[92..96]),
-        read32(&state[96..100]),
-        read32(&state[100..104]),
-        read32(&state[104..108]),
-        read32(&state[108..112]),
-        read32(&state[112..116]),
-        read32(&state[116..120]),
-        read32(&state[120..124]),
-        read32(&state[124..128]),
-        read32(&state[128..132]),
-        read32(&state[132..136]),
-        read32(&state[136..140]),
-        read32(&state[140..144]),
-        read32(&state[144..148]),
-        read32(&state[148..152]),
-        read32(&state[152..156]),
-        read32(&state[156..160]),
-        read32(&state[160..164]),
-        read32(&state[164..168]),
-        read32(&state[168..172]),
-        read32(&state[172..176]),
-        read32(&state[176..180]),
-        read32(&state[180..184]),
-        read32(&state[184..188]),
-        read32(&state[188..192]),
-    ];
-
-    for &round_key in &ROUND_KEYS {
-        // theta
-        let mut p0a = x[0] ^ x[4] ^ x[8];
-        let mut p1a = x[1] ^ x[5] ^ x[9];
-        let mut p2a = x[2] ^ x[6] ^ x[10];
-        let mut p3a = x[3] ^ x[7] ^ x[11];
-        let mut p0b = x[12] ^ x[16] ^ x[20];
-        let mut p1b = x[13] ^ x[17] ^ x[21];
-        let mut p2b = x[14] ^ x[18] ^ x[22];
-        let mut p3b = x[15] ^ x[19] ^ x[23];
-        let mut p0c = x[24] ^ x[28] ^ x[32];
-        let mut p1c = x[25] ^ x[29] ^ x[33];
-        let mut p2c = x[26] ^ x[30] ^ x[34];
-        let mut p3c = x[27] ^ x[31] ^ x[35];
-        let mut p0d = x[36] ^ x[40] ^ x[44];
-        let mut p1d = x[37] ^ x[41] ^ x[45];
-        let mut p2d = x[38] ^ x[42] ^ x[46];
-        let mut p3d = x[39] ^ x[43] ^ x[47];
-        p0a = p0a.rotate_left(5) ^ p0a.rotate_left(14);
-        p1a = p1a.rotate_left(5) ^ p1a.rotate_left(14);
-        p2a = p2a.rotate_left(5) ^ p2a.rotate_left(14);
-        p3a = p3a.rotate_left(5) ^ p3a.rotate_left(14);
-        p0b = p0b.rotate_left(5) ^ p0b.rotate_left(14);
-        p1b = p1b.rotate_left(5) ^ p1b.rotate_left(14);
-        p2b = p2b.rotate_left(5) ^ p2b.rotate_left(14);
-        p3b = p3b.rotate_left(5) ^ p3b.rotate_left(14);
-        p0c = p0c.rotate_left(5) ^ p0c.rotate_left(14);
-        p1c = p1c.rotate_left(5) ^ p1c.rotate_left(14);
-        p2c = p2c.rotate_left(5) ^ p2c.rotate_left(14);
-        p3c = p3c.rotate_left(5) ^ p3c.rotate_left(14);
-        p0d = p0d.rotate_left(5) ^ p0d.rotate_left(14);
-        p1d = p1d.rotate_left(5) ^ p1d.rotate_left(14);
-        p2d = p2d.rotate_left(5) ^ p2d.rotate_left(14);
-        p3d = p3d.rotate_left(5) ^ p3d.rotate_left(14);
-        (p0a, p1a, p2a, p3a) = (p1a, p2a, p3a, p0a);
-        (p0b, p1b, p2b, p3b) = (p1b, p2b, p3b, p0b);
-        (p0c, p1c, p2c, p3c) = (p1c, p2c, p3c, p0c);
-        (p0d, p1d, p2d, p3d) = (p1d, p2d, p3d, p0d);
-        x[0] ^= p0a;
-        x[4] ^= p0a;
-        x[8] ^= p0a;
-        x[1] ^= p1a;
-        x[5] ^= p1a;
-        x[9] ^= p1a;
-        x[2] ^= p2a;
-        x[6] ^= p2a;
-        x[10] ^= p2a;
-        x[3] ^= p3a;
-        x[7] ^= p3a;
-        x[11] ^= p3a;
-        x[12] ^= p0b;
-        x[16] ^= p0b;
-        x[20] ^= p0b;
-        x[13] ^= p1b;
-        x[17] ^= p1b;
-        x[21] ^= p1b;
-        x[14] ^= p2b;
-        x[18] ^= p2b;
-        x[22] ^= p2b;
-        x[15] ^= p3b;
-        x[19] ^= p3b;
-        x[23] ^= p3b;
-        x[24] ^= p0c;
-        x[28] ^= p0c;
-        x[32] ^= p0c;
-        x[25] ^= p1c;
-        x[29] ^= p1c;
-        x[33] ^= p1c;
-        x[26] ^= p2c;
-        x[30] ^= p2c;
-        x[34] ^= p2c;
-        x[27] ^= p3c;
-        x[31] ^= p3c;
-        x[35] ^= p3c;
-        x[36] ^= p0d;
-        x[40] ^= p0d;
-        x[44] ^= p0d;
-        x[37] ^= p1d;
-        x[41] ^= p1d;
-        x[45] ^= p1d;
-        x[38] ^= p2d;
-        x[42] ^= p2d;
-        x[46] ^= p2d;
-        x[39] ^= p3d;
-        x[43] ^= p3d;
-        x[47] ^= p3d;
-
-        // rho west
-        (x[4], x[5], x[6], x[7]) = (x[7], x[4], x[5], x[6]);
-        (x[16], x[17], x[18], x[19]) = (x[19], x[16], x[17], x[18]);
-        (x[28], x[29], x[30], x[31]) = (x[31], x[28], x[29], x[30]);
-        (x[40], x[41], x[42], x[43]) = (x[43], x[40], x[41], x[42]);
-        x[8] = x[8].rotate_left(11);
-        x[9] = x[9].rotate_left(11);
-        x[10] = x[10].rotate_left(11);
-        x[11] = x[11].rotate_left(11);
-        x[20] = x[20].rotate_left(11);
-        x[21] = x[21].rotate_left(11);
-        x[22] = x[22].rotate_left(11);
-        x[23] = x[23].rotate_left(11);
-        x[32] = x[32].rotate_left(11);
-        x[33] = x[33].rotate_left(11);
-        x[34] = x[34].rotate_left(11);
-        x[35] = x[35].rotate_left(11);
-        x[44] = x[44].rotate_left(11);
-        x[45] = x[45].rotate_left(11);
-        x[46] = x[46].rotate_left(11);
-        x[47] = x[47].rotate_left(11);
-
-        // iota
-        x[0] ^= round_key;
-        x[12] ^= round_key;
-        x[24] ^= round_key;
-        x[36] ^= round_key;
-
-        // chi
-        let t0 = x[0] ^ (!x[4] & x[8]);
-        let t1 = x[4] ^ (!x[8] & x[0]);
-        let t2 = x[8] ^ (!x[0] & x[4]);
-        x[0] = t0;
-        x[4] = t1;
-        x[8] = t2;
-        let t0 = x[1] ^ (!x[5] & x[9]);
-        let t1 = x[5] ^ (!x[9] & x[1]);
-        let t2 = x[9] ^ (!x[1] & x[5]);
-        x[1] = t0;
-        x[5] = t1;
-        x[9] = t2;
-        let t0 = x[2] ^ (!x[6] & x[10]);
-        let t1 = x[6] ^ (!x[10] & x[2]);
-        let t2 = x[10] ^ (!x[2] & x[6]);
-        x[2] = t0;
-        x[6] = t1;
-        x[10] = t2;
-        let t0 = x[3] ^ (!x[7] & x[11]);
-        let t1 = x[7] ^ (!x[11] & x[3]);
-        let t2 = x[11] ^ (!x[3] & x[7]);
-        x[3] = t0;
-        x[7] = t1;
-        x[11] = t2;
-        let t0 = x[12] ^ (!x[16] & x[20]);
-        let t1 = x[16] ^ (!x[20] & x[12]);
-        let t2 = x[20] ^ (!x[12] & x[16]);
-        x[12] = t0;
-        x[16] = t1;
-        x[20] = t2;
-        let t0 = x[13] ^ (!x[17] & x[21]);
-        let t1 = x[17] ^ (!x[21] & x[13]);
-        let t2 = x[21] ^ (!x[13] & x[17]);
-        x[13] = t0;
-        x[17] = t1;
-        x[21] = t2;
-        let t0 = x[14] ^ (!x[18] & x[22]);
-        let t1 = x[18] ^ (!x[22] & x[14]);
-        let t2 = x[22] ^ (!x[14] & x[18]);
-        x[14] = t0;
-        x[18] = t1;
-        x[22] = t2;
-        let t0 = x[15] ^ (!x[19] & x[23]);
-        let t1 = x[19] ^ (!x[23] & x[15]);
-        let t2 = x[23] ^ (!x[15] & x[19]);
-        x[15] = t0;
-        x[19] = t1;
-        x[23] = t2;
-        let t0 = x[24] ^ (!x[28] & x[32]);
-        let t1 = x[28] ^ (!x[32] & x[24]);
-        let t2 = x[32] ^ (!x[24] & x[28]);
-        x[24] = t0;
-        x[28] = t1;
-        x[32] = t2;
-        let t0 = x[25] ^ (!x[29] & x[33]);
-        let t1 = x[29] ^ (!x[33] & x[25]);
-        let t2 = x[33] ^ (!x[25] & x[29]);
-        x[25] = t0;
-        x[29] = t1;
-        x[33] = t2;
-        let t0 = x[26] ^ (!x[30] & x[34]);
-        let t1 = x[30] ^ (!x[34] & x[26]);
-        let t2 = x[34] ^ (!x[26] & x[30]);
-        x[26] = t0;
-        x[30] = t1;
-        x[34] = t2;
-        let t0 = x[27] ^ (!x[31] & x[35]);
-        let t1 = x[31] ^ (!x[35] & x[27]);
-        let t2 = x[35] ^ (!x[27] & x[31]);
-        x[27] = t0;
-        x[31] = t1;
-        x[35] = t2;
-        let t0 = x[36] ^ (!x[40] & x[44]);
-        let t1 = x[40] ^ (!x[44] & x[36]);
-        let t2 = x[44] ^ (!x[36] & x[40]);
-        x[36] = t0;
-        x[40] = t1;
-        x[44] = t2;
-        let t0 = x[37] ^ (!x[41] & x[45]);
-        let t1 = x[41] ^ (!x[45] & x[37]);
-        let t2 = x[45] ^ (!x[37] & x[41]);
-        x[37] = t0;
-        x[41] = t1;
-        x[45] = t2;
-        let t0 = x[38] ^ (!x[42] & x[46]);
-        let t1 = x[42] ^ (!x[46] & x[38]);
-        let t2 = x[46] ^ (!x[38] & x[42]);
-        x[38] = t0;
-        x[42] = t1;
-        x[46] = t2;
-        let t0 = x[39] ^ (!x[43] & x[47]);
-        let t1 = x[43] ^ (!x[47] & x[39]);
-        let t2 = x[47] ^ (!x[39] & x[43]);
-        x[39] = t0;
-        x[43] = t1;
-        x[47] = t2;
-
-        // rho east
-        x[4] = x[4].rotate_left(1);
-        x[5] = x[5].rotate_left(1);
-        x[6] = x[6].rotate_left(1);
-        x[7] = x[7].rotate_left(1);
-        x[16] = x[16].rotate_left(1);
-        x[17] = x[17].rotate_left(1);
-        x[18] = x[18].rotate_left(1);
-        x[19] = x[19].rotate_left(1);
-        x[28] = x[28].rotate_left(1);
-        x[29] = x[29].rotate_left(1);
-        x[30] = x[30].rotate_left(1);
-        x[31] = x[31].rotate_left(1);
-        x[40] = x[40].rotate_left(1);
-        x[41] = x[41].rotate_left(1);
-        x[42] = x[42].rotate_left(1);
-        x[43] = x[43].rotate_left(1);
-        (x[8], x[9], x[10], x[11]) = (x[10], x[11], x[8], x[9]);
-        (x[20], x[21], x[22], x[23]) = (x[22], x[23], x[20], x[21]);
-        (x[32], x[33], x[34], x[35]) = (x[34], x[35], x[32], x[33]);
-        (x[44], x[45], x[46], x[47]) = (x[46], x[47], x[44], x[45]);
-        x[8] = x[8].rotate_left(8);
-        x[9] = x[9].rotate_left(8);
-        x[10] = x[10].rotate_left(8);
-        x[11] = x[11].rotate_left(8);
-        x[20] = x[20].rotate_left(8);
-        x[21] = x[21].rotate_left(8);
-        x[22] = x[22].rotate_left(8);
-        x[23] = x[23].rotate_left(8);
-        x[32] = x[32].rotate_left(8);
-        x[33] = x[33].rotate_left(8);
-        x[34] = x[34].rotate_left(8);
-        x[35] = x[35].rotate_left(8);
-        x[44] = x[44].rotate_left(8);
-        x[45] = x[45].rotate_left(8);
-        x[46] = x[46].rotate_left(8);
-        x[47] = x[47].rotate_left(8);
-    }
-
-    write32(x[0], &mut state[0..4]);
-    write32(x[1], &mut state[4..8]);
-    write32(x[2], &mut state[8..12]);
-    write32(x[3], &mut state[12..16]);
-    write32(x[4], &mut state[16..20]);
-    write32(x[5], &mut state[20..24]);
-    write32(x[6], &mut state[24..28]);
-    write32(x[7], &mut state[28..32]);
-    write32(x[8], &mut state[32..36]);
-    write32(x[9], &mut state[36..40]);
-    write32(x[10], &mut state[40..44]);
-    write32(x[11], &mut state[44..48]);
-    write32(x[12], &mut state[48..52]);
-    write32(x[13], &mut state[52..56]);
-    write32(x[14], &mut state[56..60]);
-    write32(x[15], &mut state[60..64]);
-    write32(x[16], &mut state[64..68]);
-    write32(x[17], &mut state[68..72]);
-    write32(x[18], &mut state[72..76]);
-    write32(x[19], &mut state[76..80]);
-    write32(x[20], &mut state[80..84]);
-    write32(x[21], &mut state[84..88]);
-    write32(x[22], &mut state[88..92]);
-    write32(x[23], &mut state[92..96]);
-    write32(x[24], &mut state[96..100]);
-    write32(x[25], &mut state[100..104]);
-    write32(x[26], &mut state[104..108]);
-    write32(x[27], &mut state[108..112]);
-    write32(x[28], &mut state[112..116]);
-    write32(x[29], &mut state[116..120]);
-    write32(x[30], &mut state[120..124]);
-    write32(x[31], &mut state[124..128]);
-    write32(x[32], &mut state[128..132]);
-    write32(x[33], &mut state[132..136]);
-    write32(x[34], &mut state[136..140]);
-    write32(x[35], &mut state[140..144]);
-    write32(x[36], &mut state[144..148]);
-    write32(x[37], &mut state[148..152]);
-    write32(x[38], &mut state[152..156]);
-    write32(x[39], &mut state[156..160]);
-    write32(x[40], &mut state[160..164]);
-    write32(x[41], &mut state[164..168]);
-    write32(x[42], &mut state[168..172]);
-    write32(x[43], &mut state[172..176]);
-    write32(x[44], &mut state[176..180]);
-    write32(x[45], &mut state[180..184]);
-    write32(x[46], &mut state[184..188]);
-    write32(x[47], &mut state[188..192]);
+    scalar::xoodoo_scalar_traced(as_states::<4>(state), &ROUND_KEYS, &mut NoTrace);
 }
 
 /// A variant of Xoodoo that is based on 64-bit words.
 #[inline(never)]
 fn xoodoo64_scalar(state: &mut [u8; 48]) {
-    let mut x = [
-        read64(&state[0..8]),
-        read64(&state[8..16]),
-        read64(&state[16..24]),
-        read64(&state[24..32]),
-        read64(&state[32..40]),
-        read64(&state[40..48]),
-    ];
-
-    for &round_key in &ROUND_KEYS {
-        // theta
-        let mut p0 = x[0] ^ x[2] ^ x[4];
-        let mut p1 = x[1] ^ x[3] ^ x[5];
-        p0 = p0.rotate_left(10) ^ p0.rotate_left(29);
-        p1 = p1.rotate_left(10) ^ p1.rotate_left(29);
-        (p0, p1) = (p1, p0);
-
-        x[0] ^= p0;
-        x[2] ^= p0;
-        x[4] ^= p0;
-        x[1] ^= p1;
-        x[3] ^= p1;
-        x[5] ^= p1;
-
-        // rho west
-        x.swap(2, 3);
-        x[4] = x[4].rotate_left(23);
-        x[5] = x[5].rotate_left(23);
-
-        // iota
-        x[0] ^= round_key as u64;
-
-        // chi
-        let t0 = x[0] ^ (!x[2] & x[4]);
-        let t1 = x[2] ^ (!x[4] & x[0]);
-        let t2 = x[4] ^ (!x[0] & x[2]);
-        x[0] = t0;
-        x[2] = t1;
-        x[4] = t2;
-        let t0 = x[1] ^ (!x[3] & x[5]);
-        let t1 = x[3] ^ (!x[5] & x[1]);
-        let t2 = x[5] ^ (!x[1] & x[3]);
-        x[1] = t0;
-        x[3] = t1;
-        x[5] = t2;
-
-        // rho east
-        x[2] = x[2].rotate_left(1);
-        x[3] = x[3].rotate_left(1);
-        x.swap(4, 5);
-        x[4] = x[4].rotate_left(16);
-        x[5] = x[5].rotate_left(16);
-    }
+    xoodoo64_scalar_rounds(state, &constants::WIDENED);
+}
 
-    write64(x[0], &mut state[0..8]);
-    write64(x[1], &mut state[8..16]);
-    write64(x[2], &mut state[16..24]);
-    write64(x[3], &mut state[24..32]);
-    write64(x[4], &mut state[32..40]);
-    write64(x[5], &mut state[40..48]);
+/// `xoodoo64_scalar` with Keccak's round constants, which reach bit 63 of the lane.
+#[inline(never)]
+fn xoodoo64_scalar_lfsr(state: &mut [u8; 48]) {
+    xoodoo64_scalar_rounds(state, &constants::LFSR);
+}
+
+/// `xoodoo64_scalar` with the given round constants, one round per constant.
+#[inline(always)]
+fn xoodoo64_scalar_rounds(state: &mut [u8; 48], round_keys: &[u64]) {
+    scalar::xoodoo64_scalar_traced(std::array::from_mut(state), round_keys, &mut NoTrace);
+}
+
+/// `N` interleaved instances of `xoodoo64_scalar`.
+#[inline(never)]
+fn xoodoo64_scalar_xn<const N: usize>(states: &mut [[u8; 48]; N]) {
+    scalar::xoodoo64_scalar_traced(states, &constants::WIDENED, &mut NoTrace);
+}
+
+/// `xoodoo64_scalar` on 32-bit words; see `bitinterleaved`.
+#[inline(never)]
+fn xoodoo64_bitinterleaved(state: &mut [u8; 48]) {
+    xoodoo64_bitinterleaved_rounds(state, &bitinterleaved::WIDENED);
+}
+
+/// `xoodoo64_bitinterleaved` with the given split round constants.
+#[inline(always)]
+fn xoodoo64_bitinterleaved_rounds(state: &mut [u8; 48], round_keys: &[bitinterleaved::Lane]) {
+    xoodoo64_bitinterleaved_traced(state, round_keys, &mut NoTrace);
+}
+
+/// `xoodoo64_bitinterleaved_rounds`, recording the state after every step.
+#[inline(always)]
+fn xoodoo64_bitinterleaved_traced(
+    state: &mut [u8; 48],
+    round_keys: &[bitinterleaved::Lane],
+    tracer: &mut impl trace::Tracer,
+) {
+    let mut a = bitinterleaved::load(state);
+    bitinterleaved::permute(&mut a, round_keys, tracer);
+    bitinterleaved::store(&a, state);
 }
 
 #[inline(never)]
 fn xoodoo64_scalar_x2(state: &mut [u8; 96]) {
-    let mut x = [
-        read64(&state[0..8]),
-        read64(&state[8..16]),
-        read64(&state[16..24]),
-        read64(&state[24..32]),
-        read64(&state[32..40]),
-        read64(&state[40..48]),
-        read64(&state[48..56]),
-        read64(&state[56..64]),
-        read64(&state[64..72]),
-        read64(&state[72..80]),
-        read64(&state[80..88]),
-        read64(&state[88..96]),
-    ];
-
-    for &round_key in &ROUND_KEYS {
-        // theta
-        let mut p0a = x[0] ^ x[2] ^ x[4];
-        let mut p1a = x[1] ^ x[3] ^ x[5];
-        let mut p0b = x[6] ^ x[8] ^ x[10];
-        let mut p1b = x[7] ^ x[9] ^ x[11];
-        p0a = p0a.rotate_left(10) ^ p0a.rotate_left(29);
-        p1a = p1a.rotate_left(10) ^ p1a.rotate_left(29);
-        p0b = p0b.rotate_left(10) ^ p0b.rotate_left(29);
-        p1b = p1b.rotate_left(10) ^ p1b.rotate_left(29);
-        (p0a, p1a) = (p1a, p0a);
-        (p0b, p1b) = (p1b, p0b);
-
-        x[0] ^= p0a;
-        x[2] ^= p0a;
-        x[4] ^= p0a;
-        x[1] ^= p1a;
-        x[3] ^= p1a;
-        x[5] ^= p1a;
-        x[6] ^= p0b;
-        x[8] ^= p0b;
-        x[10] ^= p0b;
-        x[7] ^= p1b;
-        x[9] ^= p1b;
-        x[11] ^= p1b;
-
-        // rho west
-        x.swap(2, 3);
-        x.swap(8, 9);
-        x[4] = x[4].rotate_left(23);
-        x[5] = x[5].rotate_left(23);
-        x[10] = x[10].rotate_left(23);
-        x[11] = x[11].rotate_left(23);
-
-        // iota
-        x[0] ^= round_key as u64;
-        x[6] ^= round_key as u64;
-        // chi
-        let t0 = x[0] ^ (!x[2] & x[4]);
-        let t1 = x[2] ^ (!x[4] & x[0]);
-        let t2 = x[4] ^ (!x[0] & x[2]);
-        x[0] = t0;
-        x[2] = t1;
-        x[4] = t2;
-        let t0 = x[1] ^ (!x[3] & x[5]);
-        let t1 = x[3] ^ (!x[5] & x[1]);
-        let t2 = x[5] ^ (!x[1] & x[3]);
-        x[1] = t0;
-        x[3] = t1;
-        x[5] = t2;
-        let t0 = x[6] ^ (!x[8] & x[10]);
-        let t1 = x[8] ^ (!x[10] & x[6]);
-        let t2 = x[10] ^ (!x[6] & x[8]);
-        x[6] = t0;
-        x[8] = t1;
-        x[10] = t2;
-        let t0 = x[7] ^ (!x[9] & x[11]);
-        let t1 = x[9] ^ (!x[11] & x[7]);
-        let t2 = x[11] ^ (!x[7] & x[9]);
-        x[7] = t0;
-        x[9] = t1;
-        x[11] = t2;
-
-        // rho east
-        x[2] = x[2].rotate_left(1);
-        x[3] = x[3].rotate_left(1);
-        x[8] = x[8].rotate_left(1);
-        x[9] = x[9].rotate_left(1);
-        x.swap(4, 5);
-        x.swap(10, 11);
-        x[4] = x[4].rotate_left(16);
-        x[5] = x[5].rotate_left(16);
-        x[10] = x[10].rotate_left(16);
-        x[11] = x[11].rotate_left(16);
+    scalar::xoodoo64_scalar_traced(as_states::<2>(state), &constants::WIDENED, &mut NoTrace);
+}
+
+#[inline(never)]
+fn xoodoo64_scalar_x4(state: &mut [u8; 192]) {
+    scalar::xoodoo64_scalar_traced(as_states::<4>(state), &constants::WIDENED, &mut NoTrace);
+}
+
+/// Xoodoo on `N` states kept grouped by word between calls; see
+/// `scalar::xoodoo_interleave` and `scalar::xoodoo_deinterleave`.
+#[inline(never)]
+fn xoodoo_permute_interleaved<const N: usize>(x: &mut scalar::XoodooInterleaved<N>) {
+    scalar::xoodoo_interleaved_traced(x, &ROUND_KEYS, &mut NoTrace);
+}
+
+/// `xoodoo_permute_interleaved` for Xoodoo64.
+#[inline(never)]
+fn xoodoo64_permute_interleaved<const N: usize>(x: &mut scalar::Xoodoo64Interleaved<N>) {
+    scalar::xoodoo64_interleaved_traced(x, &constants::WIDENED, &mut NoTrace);
+}
+
+/// Applies Xoodoo `k` times to each of `N` states, loading and storing them once
+/// rather than around every permutation.
+#[inline(never)]
+fn xoodoo_permute_k_times<const N: usize>(states: &mut [[u8; 48]; N], k: usize) {
+    let mut x = scalar::xoodoo_interleave(states);
+    for _ in 0..k {
+        scalar::xoodoo_interleaved_traced(&mut x, &ROUND_KEYS, &mut NoTrace);
     }
+    scalar::xoodoo_deinterleave(&x, states);
+}
 
-    write64(x[0], &mut state[0..8]);
-    write64(x[1], &mut state[8..16]);
-    write64(x[2], &mut state[16..24]);
-    write64(x[3], &mut state[24..32]);
-    write64(x[4], &mut state[32..40]);
-    write64(x[5], &mut state[40..48]);
-    write64(x[6], &mut state[48..56]);
-    write64(x[7], &mut state[56..64]);
-    write64(x[8], &mut state[64..72]);
-    write64(x[9], &mut state[72..80]);
-    write64(x[10], &mut state[80..88]);
-    write64(x[11], &mut state[88..96]);
+/// `xoodoo_permute_k_times` for Xoodoo64.
+#[inline(never)]
+fn xoodoo64_permute_k_times<const N: usize>(states: &mut [[u8; 48]; N], k: usize) {
+    let mut x = scalar::xoodoo64_interleave(states);
+    for _ in 0..k {
+        scalar::xoodoo64_interleaved_traced(&mut x, &constants::WIDENED, &mut NoTrace);
+    }
+    scalar::xoodoo64_deinterleave(&x, states);
 }
 
+/// Fills `out` by permuting `state` with Xoodoo and extracting the first `rate`
+/// bytes, keeping the state in registers in between. `rate` must be 1 to 48.
 #[inline(never)]
-fn xoodoo64_scalar_x4(state: &mut [u8; 192]) {
-    let mut x = [
-        read64(&state[0..8]),
-        read64(&state[8..16]),
-        read64(&state[16..24]),
-        read64(&state[24..32]),
-        read64(&state[32..40]),
-        read64(&state[40..48]),
-        read64(&state[48..56]),
-        read64(&state[56..64]),
-        read64(&state[64..72]),
-        read64(&state[72..80]),
-        read64(&state[80..88]),
-        read64(&state[88..96]),
-        read64(&state[96..104]),
-        read64(&state[104..112]),
-        read64(&state[112..120]),
-        read64(&state[120..128]),
-        read64(&state[128..136]),
-        read64(&state[136..144]),
-        read64(&state[144..152]),
-        read64(&state[152..160]),
-        read64(&state[160..168]),
-        read64(&state[168..176]),
-        read64(&state[176..184]),
-        read64(&state[184..192]),
-    ];
-
-    for &round_key in &ROUND_KEYS {
-        // theta
-        let mut p0a = x[0] ^ x[2] ^ x[4];
-        let mut p1a = x[1] ^ x[3] ^ x[5];
-        let mut p0b = x[6] ^ x[8] ^ x[10];
-        let mut p1b = x[7] ^ x[9] ^ x[11];
-        let mut p0c = x[12] ^ x[14] ^ x[16];
-        let mut p1c = x[13] ^ x[15] ^ x[17];
-        let mut p0d = x[18] ^ x[20] ^ x[22];
-        let mut p1d = x[19] ^ x[21] ^ x[23];
-        p0a = p0a.rotate_left(10) ^ p0a.rotate_left(29);
-        p1a = p1a.rotate_left(10) ^ p1a.rotate_left(29);
-        p0b = p0b.rotate_left(10) ^ p0b.rotate_left(29);
-        p1b = p1b.rotate_left(10) ^ p1b.rotate_left(29);
-        p0c = p0c.rotate_left(10) ^ p0c.rotate_left(29);
-        p1c = p1c.rotate_left(10) ^ p1c.rotate_left(29);
-        p0d = p0d.rotate_left(10) ^ p0d.rotate_left(29);
-        p1d = p1d.rotate_left(10) ^ p1d.rotate_left(29);
-        (p0a, p1a) = (p1a, p0a);
-        (p0b, p1b) = (p1b, p0b);
-        (p0c, p1c) = (p1c, p0c);
-        (p0d, p1d) = (p1d, p0d);
-
-        x[0] ^= p0a;
-        x[2] ^= p0a;
-        x[4] ^= p0a;
-        x[1] ^= p1a;
-        x[3] ^= p1a;
-        x[5] ^= p1a;
-        x[6] ^= p0b;
-        x[8] ^= p0b;
-        x[10] ^= p0b;
-        x[7] ^= p1b;
-        x[9] ^= p1b;
-        x[11] ^= p1b;
-        x[12] ^= p0c;
-        x[14] ^= p0c;
-        x[16] ^= p0c;
-        x[13] ^= p1c;
-        x[15] ^= p1c;
-        x[17] ^= p1c;
-        x[18] ^= p0d;
-        x[20] ^= p0d;
-        x[22] ^= p0d;
-        x[19] ^= p1d;
-        x[21] ^= p1d;
-        x[23] ^= p1d;
-
-        // rho west
-        x.swap(2, 3);
-        x.swap(8, 9);
-        x.swap(14, 15);
-        x.swap(20, 21);
-        x[4] = x[4].rotate_left(23);
-        x[5] = x[5].rotate_left(23);
-        x[10] = x[10].rotate_left(23);
-        x[11] = x[11].rotate_left(23);
-        x[16] = x[16].rotate_left(23);
-        x[17] = x[17].rotate_left(23);
-        x[22] = x[22].rotate_left(23);
-        x[23] = x[23].rotate_left(23);
-
-        // iota
-        x[0] ^= round_key as u64;
-        x[6] ^= round_key as u64;
-        x[12] ^= round_key as u64;
-        x[18] ^= round_key as u64;
-
-        // chi
-        let t0 = x[0] ^ (!x[2] & x[4]);
-        let t1 = x[2] ^ (!x[4] & x[0]);
-        let t2 = x[4] ^ (!x[0] & x[2]);
-        x[0] = t0;
-        x[2] = t1;
-        x[4] = t2;
-        let t0 = x[1] ^ (!x[3] & x[5]);
-        let t1 = x[3] ^ (!x[5] & x[1]);
-        let t2 = x[5] ^ (!x[1] & x[3]);
-        x[1] = t0;
-        x[3] = t1;
-        x[5] = t2;
-        let t0 = x[6] ^ (!x[8] & x[10]);
-        let t1 = x[8] ^ (!x[10] & x[6]);
-        let t2 = x[10] ^ (!x[6] & x[8]);
-        x[6] = t0;
-        x[8] = t1;
-        x[10] = t2;
-        let t0 = x[7] ^ (!x[9] & x[11]);
-        let t1 = x[9] ^ (!x[11] & x[7]);
-        let t2 = x[11] ^ (!x[7] & x[9]);
-        x[7] = t0;
-        x[9] = t1;
-        x[11] = t2;
-        let t0 = x[12] ^ (!x[14] & x[16]);
-        let t1 = x[14] ^ (!x[16] & x[12]);
-        let t2 = x[16] ^ (!x[12] & x[14]);
-        x[12] = t0;
-        x[14] = t1;
-        x[16] = t2;
-        let t0 = x[13] ^ (!x[15] & x[17]);
-        let t1 = x[15] ^ (!x[17] & x[13]);
-        let t2 = x[17] ^ (!x[13] & x[15]);
-        x[13] = t0;
-        x[15] = t1;
-        x[17] = t2;
-        let t0 = x[18] ^ (!x[20] & x[22]);
-        let t1 = x[20] ^ (!x[22] & x[18]);
-        let t2 = x[22] ^ (!x[18] & x[20]);
-        x[18] = t0;
-        x[20] = t1;
-        x[22] = t2;
-        let t0 = x[19] ^ (!x[21] & x[23]);
-        let t1 = x[21] ^ (!x[23] & x[19]);
-        let t2 = x[23] ^ (!x[19] & x[21]);
-        x[19] = t0;
-        x[21] = t1;
-        x[23] = t2;
-
-        // rho east
-        x[2] = x[2].rotate_left(1);
-        x[3] = x[3].rotate_left(1);
-        x[8] = x[8].rotate_left(1);
-        x[9] = x[9].rotate_left(1);
-        x[14] = x[14].rotate_left(1);
-        x[15] = x[15].rotate_left(1);
-        x[20] = x[20].rotate_left(1);
-        x[21] = x[21].rotate_left(1);
-        x.swap(4, 5);
-        x.swap(10, 11);
-        x.swap(16, 17);
-        x.swap(22, 23);
-        x[4] = x[4].rotate_left(16);
-        x[5] = x[5].rotate_left(16);
-        x[10] = x[10].rotate_left(16);
-        x[11] = x[11].rotate_left(16);
-        x[16] = x[16].rotate_left(16);
-        x[17] = x[17].rotate_left(16);
-        x[22] = x[22].rotate_left(16);
-        x[23] = x[23].rotate_left(16);
+fn xoodoo_squeeze(state: &mut [u8; 48], rate: usize, out: &mut [u8]) {
+    assert!((1..=48).contains(&rate));
+    let states = std::array::from_mut(state);
+    let mut x = scalar::xoodoo_interleave(states);
+    let mut output = [[0u8; 48]];
+    for block in out.chunks_mut(rate) {
+        scalar::xoodoo_interleaved_traced(&mut x, &ROUND_KEYS, &mut NoTrace);
+        scalar::xoodoo_deinterleave(&x, &mut output);
+        block.copy_from_slice(&output[0][..block.len()]);
     }
+    scalar::xoodoo_deinterleave(&x, states);
+}
+
+/// `xoodoo_squeeze` for Xoodoo64.
+#[inline(never)]
+fn xoodoo64_squeeze(state: &mut [u8; 48], rate: usize, out: &mut [u8]) {
+    assert!((1..=48).contains(&rate));
+    let states = std::array::from_mut(state);
+    let mut x = scalar::xoodoo64_interleave(states);
+    let mut output = [[0u8; 48]];
+    for block in out.chunks_mut(rate) {
+        scalar::xoodoo64_interleaved_traced(&mut x, &constants::WIDENED, &mut NoTrace);
+        scalar::xoodoo64_deinterleave(&x, &mut output);
+        block.copy_from_slice(&output[0][..block.len()]);
+    }
+    scalar::xoodoo64_deinterleave(&x, states);
+}
 
-    write64(x[0], &mut state[0..8]);
-    write64(x[1], &mut state[8..16]);
-    write64(x[2], &mut state[16..24]);
-    write64(x[3], &mut state[24..32]);
-    write64(x[4], &mut state[32..40]);
-    write64(x[5], &mut state[40..48]);
-    write64(x[6], &mut state[48..56]);
-    write64(x[7], &mut state[56..64]);
-    write64(x[8], &mut state[64..72]);
-    write64(x[9], &mut state[72..80]);
-    write64(x[10], &mut state[80..88]);
-    write64(x[11], &mut state[88..96]);
-    write64(x[12], &mut state[96..104]);
-    write64(x[13], &mut state[104..112]);
-    write64(x[14], &mut state[112..120]);
-    write64(x[15], &mut state[120..128]);
-    write64(x[16], &mut state[128..136]);
-    write64(x[17], &mut state[136..144]);
-    write64(x[18], &mut state[144..152]);
-    write64(x[19], &mut state[152..160]);
-    write64(x[20], &mut state[160..168]);
-    write64(x[21], &mut state[168..176]);
-    write64(x[22], &mut state[176..184]);
-    write64(x[23], &mut state[184..192]);
+/// Permutes any number of states with `x4`, then `x2` and `x1` for the rest.
+#[inline(always)]
+fn permute_many(
+    states: &mut [[u8; 48]],
+    x4: fn(&mut [u8; 192]),
+    x2: fn(&mut [u8; 96]),
+    x1: fn(&mut [u8; 48]),
+) {
+    let (quads, rest) = states.as_chunks_mut::<4>();
+    for quad in quads {
+        x4(quad.as_flattened_mut().try_into().unwrap());
+    }
+    let (pairs, rest) = rest.as_chunks_mut::<2>();
+    for pair in pairs {
+        x2(pair.as_flattened_mut().try_into().unwrap());
+    }
+    for state in rest {
+        x1(state);
+    }
+}
+
+/// Applies Xoodoo to every state, using the widest backend for as many as it
+/// fits: the NEON ones on aarch64 and the scalar ones elsewhere.
+fn xoodoo_permute_many(states: &mut [[u8; 48]]) {
+    #[cfg(target_arch = "aarch64")]
+    permute_many(states, xoodoo_aarch64_x4, xoodoo_aarch64_x2, xoodoo_aarch64);
+    #[cfg(not(target_arch = "aarch64"))]
+    permute_many(states, xoodoo_scalar_x4, xoodoo_scalar_x2, xoodoo_scalar);
+}
+
+/// `xoodoo_permute_many` for Xoodoo64. Its NEON backends need the SHA3
+/// extension; without it, the scalar ones are used on aarch64 too.
+fn xoodoo64_permute_many(states: &mut [[u8; 48]]) {
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("sha3") {
+        return permute_many(
+            states,
+            |s| unsafe { xoodoo64_aarch64_sha3_x4(s) },
+            |s| unsafe { xoodoo64_aarch64_sha3_x2(s) },
+            |s| unsafe { xoodoo64_aarch64_sha3(s) },
+        );
+    }
+    permute_many(
+        states,
+        xoodoo64_scalar_x4,
+        xoodoo64_scalar_x2,
+        xoodoo64_scalar,
+    );
 }
 
 #[cfg(target_arch = "aarch64")]
 #[target_feature(enable = "sha3")]
 #[inline(never)]
 unsafe fn xoodoo64_aarch64_sha3(x: &mut [u8; 48]) {
+    unsafe { xoodoo64_aarch64_sha3_traced(x, &mut NoTrace) }
+}
+
+/// `xoodoo64_aarch64_sha3`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline]
+unsafe fn xoodoo64_aarch64_sha3_traced(x: &mut [u8; 48], tracer: &mut impl Tracer) {
     use std::arch::aarch64::*;
 
     unsafe {
@@ -1702,41 +1077,45 @@ unsafe fn xoodoo64_aarch64_sha3(x: &mut [u8; 48]) {
         let mut b: uint64x2_t = transmute(read128(&x[16..32]));
         let mut c: uint64x2_t = transmute(read128(&x[32..48]));
 
-        let swap_u64s: uint8x16_t =
-            transmute([8u8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7]);
+        let swap_u64s = vld1q_u8(shuffles::SWAP_LANES.as_ptr());
         let zero: uint64x2_t = transmute([0u8; 16]);
-        let rho_east: uint8x16_t =
-            transmute([10u8, 11, 12, 13, 14, 15, 8, 9, 2, 3, 4, 5, 6, 7, 0, 1]);
+        let rho_east = vld1q_u8(shuffles::XOODOO64_RHO_EAST.as_ptr());
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let p = veor3q_u64(a, b, c);
             let p = vreinterpretq_u64_u8(vqtbl1q_u8(vreinterpretq_u8_u64(p), swap_u64s));
-            let tp = vxarq_u64::<19>(p, zero);
-            let e = vxarq_u64::<10>(p, tp);
+            // vxarq_u64 rotates right: (p ^ p <<< 19) <<< 10 is p <<< 10 ^ p <<< 29.
+            let tp = vxarq_u64::<{ 64 - 19 }>(p, zero);
+            let e = vxarq_u64::<{ 64 - 10 }>(p, tp);
             a = veorq_u64(a, e);
             b = veorq_u64(b, e);
             c = veorq_u64(c, e);
+            tracer.record(round, Step::Theta, &xoodoo64_aarch64_lanes([[a, b, c]]));
 
             // rho west
             b = vreinterpretq_u64_u8(vqtbl1q_u8(vreinterpretq_u8_u64(b), swap_u64s));
-            c = vxarq_u64::<23>(c, zero);
+            c = vxarq_u64::<{ 64 - 23 }>(c, zero);
+            tracer.record(round, Step::RhoWest, &xoodoo64_aarch64_lanes([[a, b, c]]));
 
             // iota
             let round_const = vsetq_lane_u64(round_key as u64, zero, 0);
             a = veorq_u64(a, round_const);
+            tracer.record(round, Step::Iota, &xoodoo64_aarch64_lanes([[a, b, c]]));
 
             // chi
-            let a2 = vbcaxq_u64(c, b, a);
-            let b2 = vbcaxq_u64(a, c, b);
-            let c2 = vbcaxq_u64(b, a, c);
+            let a2 = vbcaxq_u64(a, c, b);
+            let b2 = vbcaxq_u64(b, a, c);
+            let c2 = vbcaxq_u64(c, b, a);
             a = a2;
             b = b2;
             c = c2;
+            tracer.record(round, Step::Chi, &xoodoo64_aarch64_lanes([[a, b, c]]));
 
             // rho east
-            b = vxarq_u64::<1>(b, zero);
+            b = vxarq_u64::<{ 64 - 1 }>(b, zero);
             c = vreinterpretq_u64_u8(vqtbl1q_u8(vreinterpretq_u8_u64(c), rho_east));
+            tracer.record(round, Step::RhoEast, &xoodoo64_aarch64_lanes([[a, b, c]]));
         }
 
         // Store results back
@@ -1746,39 +1125,102 @@ unsafe fn xoodoo64_aarch64_sha3(x: &mut [u8; 48]) {
     }
 }
 
+/// The lanes of `N` Xoodoo states kept a plane per vector, grouped by word as a
+/// `Tracer` takes them.
 #[cfg(target_arch = "aarch64")]
-#[target_feature(enable = "sha3")]
-#[inline(never)]
-unsafe fn xoodoo64_aarch64_sha3_x2(state: &mut [u8; 96]) {
+#[inline(always)]
+fn xoodoo_aarch64_lanes<const N: usize>(planes: [[uint32x4_t; 3]; N]) -> [[u32; N]; 12] {
+    let planes = planes.map(|p| p.map(|v| unsafe { transmute::<uint32x4_t, [u32; 4]>(v) }));
+    std::array::from_fn(|j| std::array::from_fn(|i| planes[i][j / 4][j % 4]))
+}
+
+/// `xoodoo_aarch64_lanes` for Xoodoo64.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn xoodoo64_aarch64_lanes<const N: usize>(planes: [[uint64x2_t; 3]; N]) -> [[u64; N]; 6] {
+    let planes = planes.map(|p| p.map(|v| unsafe { transmute::<uint64x2_t, [u64; 2]>(v) }));
+    std::array::from_fn(|j| std::array::from_fn(|i| planes[i][j / 2][j % 2]))
+}
+
+/// The lanes of the four states of `xoodoo64_aarch64_sha3_x4_interleaved`,
+/// grouped by word as a `Tracer` takes them.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn xoodoo64_aarch64_x4_lanes(x: &[uint64x2_t; 12]) -> [[u64; 4]; 6] {
+    let x = x.map(|v| unsafe { transmute::<uint64x2_t, [u64; 2]>(v) });
+    std::array::from_fn(|j| [x[j][0], x[j][1], x[6 + j][0], x[6 + j][1]])
+}
+
+/// Transposes two Xoodoo64 states from the 48-byte layout to lane `j` of both
+/// in `x[j]`, the layout of `scalar::xoodoo64_interleave::<2>`.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn xoodoo64_aarch64_interleave(states: &[u8]) -> [uint64x2_t; 6] {
     use std::arch::aarch64::*;
 
     unsafe {
-        let mut x: [uint64x2_t; 6] = [
-            transmute(read128(&state[0..16])),
-            transmute(read128(&state[16..32])),
-            transmute(read128(&state[32..48])),
-            transmute(read128(&state[48..64])),
-            transmute(read128(&state[64..80])),
-            transmute(read128(&state[80..96])),
-        ];
-        // Transpose from grouped-by-block to grouped-by-word.
-        x = [
+        let x: [uint64x2_t; 6] =
+            std::array::from_fn(|i| transmute(read128(&states[16 * i..16 * i + 16])));
+        [
             vtrn1q_u64(x[0], x[3]),
-            vtrn1q_u64(x[1], x[4]),
-            vtrn1q_u64(x[2], x[5]),
             vtrn2q_u64(x[0], x[3]),
+            vtrn1q_u64(x[1], x[4]),
             vtrn2q_u64(x[1], x[4]),
+            vtrn1q_u64(x[2], x[5]),
             vtrn2q_u64(x[2], x[5]),
+        ]
+    }
+}
+
+/// The inverse of `xoodoo64_aarch64_interleave`.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn xoodoo64_aarch64_deinterleave(x: &[uint64x2_t; 6], states: &mut [u8]) {
+    use std::arch::aarch64::*;
+
+    unsafe {
+        let x = [
+            vtrn1q_u64(x[0], x[1]),
+            vtrn1q_u64(x[2], x[3]),
+            vtrn1q_u64(x[4], x[5]),
+            vtrn2q_u64(x[0], x[1]),
+            vtrn2q_u64(x[2], x[3]),
+            vtrn2q_u64(x[4], x[5]),
         ];
+        for (i, v) in x.into_iter().enumerate() {
+            write128(
+                transmute::<uint64x2_t, [u8; 16]>(v),
+                &mut states[16 * i..16 * i + 16],
+            );
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline(never)]
+unsafe fn xoodoo64_aarch64_sha3_x2(state: &mut [u8; 96]) {
+    unsafe { xoodoo64_aarch64_sha3_x2_traced(state, &mut NoTrace) }
+}
+
+/// `xoodoo64_aarch64_sha3_x2`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline]
+unsafe fn xoodoo64_aarch64_sha3_x2_traced(state: &mut [u8; 96], tracer: &mut impl Tracer) {
+    use std::arch::aarch64::*;
+
+    unsafe {
+        let mut x = xoodoo64_aarch64_interleave(state);
 
         let zero: uint64x2_t = transmute([0u8; 16]);
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p0 = veor3q_u64(x[0], x[2], x[4]);
             let mut p1 = veor3q_u64(x[1], x[3], x[5]);
-            p0 = vxarq_u64::<10>(p0, vxarq_u64::<19>(p0, zero));
-            p1 = vxarq_u64::<10>(p1, vxarq_u64::<19>(p1, zero));
+            p0 = vxarq_u64::<{ 64 - 10 }>(p0, vxarq_u64::<{ 64 - 19 }>(p0, zero));
+            p1 = vxarq_u64::<{ 64 - 10 }>(p1, vxarq_u64::<{ 64 - 19 }>(p1, zero));
             (p0, p1) = (p1, p0);
 
             x[0] = veorq_u64(x[0], p0);
@@ -1787,54 +1229,63 @@ unsafe fn xoodoo64_aarch64_sha3_x2(state: &mut [u8; 96]) {
             x[1] = veorq_u64(x[1], p1);
             x[3] = veorq_u64(x[3], p1);
             x[5] = veorq_u64(x[5], p1);
+            tracer.record(
+                round,
+                Step::Theta,
+                &transmute::<[uint64x2_t; 6], [[u64; 2]; 6]>(x),
+            );
 
             // rho west
             x.swap(2, 3);
-            x[4] = vxarq_u64::<23>(x[4], zero);
-            x[5] = vxarq_u64::<23>(x[5], zero);
+            x[4] = vxarq_u64::<{ 64 - 23 }>(x[4], zero);
+            x[5] = vxarq_u64::<{ 64 - 23 }>(x[5], zero);
+            tracer.record(
+                round,
+                Step::RhoWest,
+                &transmute::<[uint64x2_t; 6], [[u64; 2]; 6]>(x),
+            );
 
             // iota
             x[0] = veorq_u64(x[0], vdupq_n_u64(round_key as u64));
+            tracer.record(
+                round,
+                Step::Iota,
+                &transmute::<[uint64x2_t; 6], [[u64; 2]; 6]>(x),
+            );
 
             // chi
-            let t0 = vbcaxq_u64(x[4], x[2], x[0]);
-            let t1 = vbcaxq_u64(x[0], x[4], x[2]);
-            let t2 = vbcaxq_u64(x[2], x[0], x[4]);
+            let t0 = vbcaxq_u64(x[0], x[4], x[2]);
+            let t1 = vbcaxq_u64(x[2], x[0], x[4]);
+            let t2 = vbcaxq_u64(x[4], x[2], x[0]);
             x[0] = t0;
             x[2] = t1;
             x[4] = t2;
-            let t0 = vbcaxq_u64(x[5], x[3], x[1]);
-            let t1 = vbcaxq_u64(x[1], x[5], x[3]);
-            let t2 = vbcaxq_u64(x[3], x[1], x[5]);
+            let t0 = vbcaxq_u64(x[1], x[5], x[3]);
+            let t1 = vbcaxq_u64(x[3], x[1], x[5]);
+            let t2 = vbcaxq_u64(x[5], x[3], x[1]);
             x[1] = t0;
             x[3] = t1;
             x[5] = t2;
+            tracer.record(
+                round,
+                Step::Chi,
+                &transmute::<[uint64x2_t; 6], [[u64; 2]; 6]>(x),
+            );
 
             // rho east
-            x[2] = vxarq_u64::<1>(x[2], zero);
-            x[3] = vxarq_u64::<1>(x[3], zero);
+            x[2] = vxarq_u64::<{ 64 - 1 }>(x[2], zero);
+            x[3] = vxarq_u64::<{ 64 - 1 }>(x[3], zero);
             x.swap(4, 5);
-            x[4] = vxarq_u64::<16>(x[4], zero);
-            x[5] = vxarq_u64::<16>(x[5], zero);
+            x[4] = vxarq_u64::<{ 64 - 16 }>(x[4], zero);
+            x[5] = vxarq_u64::<{ 64 - 16 }>(x[5], zero);
+            tracer.record(
+                round,
+                Step::RhoEast,
+                &transmute::<[uint64x2_t; 6], [[u64; 2]; 6]>(x),
+            );
         }
 
-        // Transpose back to grouped-by-block
-        x = [
-            vtrn1q_u64(x[0], x[3]),
-            vtrn1q_u64(x[1], x[4]),
-            vtrn1q_u64(x[2], x[5]),
-            vtrn2q_u64(x[0], x[3]),
-            vtrn2q_u64(x[1], x[4]),
-            vtrn2q_u64(x[2], x[5]),
-        ];
-
-        // Store results back
-        write128(transmute(x[0]), &mut state[0..16]);
-        write128(transmute(x[1]), &mut state[16..32]);
-        write128(transmute(x[2]), &mut state[32..48]);
-        write128(transmute(x[3]), &mut state[48..64]);
-        write128(transmute(x[4]), &mut state[64..80]);
-        write128(transmute(x[5]), &mut state[80..96]);
+        xoodoo64_aarch64_deinterleave(&x, state);
     }
 }
 
@@ -1842,51 +1293,46 @@ unsafe fn xoodoo64_aarch64_sha3_x2(state: &mut [u8; 96]) {
 #[target_feature(enable = "sha3")]
 #[inline(never)]
 unsafe fn xoodoo64_aarch64_sha3_x4(state: &mut [u8; 192]) {
-    use std::arch::aarch64::*;
+    unsafe { xoodoo64_aarch64_sha3_x4_traced(state, &mut NoTrace) }
+}
 
+/// `xoodoo64_aarch64_sha3_x4`, recording the state after every step.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline]
+unsafe fn xoodoo64_aarch64_sha3_x4_traced(state: &mut [u8; 192], tracer: &mut impl Tracer) {
+    let mut x: [[uint64x2_t; 6]; 2] =
+        std::array::from_fn(|i| xoodoo64_aarch64_interleave(&state[96 * i..]));
     unsafe {
-        let mut x: [uint64x2_t; 12] = [
-            transmute(read128(&state[0..16])),
-            transmute(read128(&state[16..32])),
-            transmute(read128(&state[32..48])),
-            transmute(read128(&state[48..64])),
-            transmute(read128(&state[64..80])),
-            transmute(read128(&state[80..96])),
-            transmute(read128(&state[96..112])),
-            transmute(read128(&state[112..128])),
-            transmute(read128(&state[128..144])),
-            transmute(read128(&state[144..160])),
-            transmute(read128(&state[160..176])),
-            transmute(read128(&state[176..192])),
-        ];
-        // Transpose from grouped-by-block to grouped-by-word.
-        x = [
-            vtrn1q_u64(x[0], x[3]),
-            vtrn1q_u64(x[1], x[4]),
-            vtrn1q_u64(x[2], x[5]),
-            vtrn2q_u64(x[0], x[3]),
-            vtrn2q_u64(x[1], x[4]),
-            vtrn2q_u64(x[2], x[5]),
-            vtrn1q_u64(x[6], x[9]),
-            vtrn1q_u64(x[7], x[10]),
-            vtrn1q_u64(x[8], x[11]),
-            vtrn2q_u64(x[6], x[9]),
-            vtrn2q_u64(x[7], x[10]),
-            vtrn2q_u64(x[8], x[11]),
-        ];
+        xoodoo64_aarch64_sha3_x4_interleaved(x.as_flattened_mut().try_into().unwrap(), tracer)
+    };
+    for (x, states) in x.iter().zip(state.chunks_exact_mut(96)) {
+        xoodoo64_aarch64_deinterleave(x, states);
+    }
+}
 
+/// Four Xoodoo64 states kept grouped by word between calls: lane `j` of states
+/// 0 and 1 in `x[j]` and of states 2 and 3 in `x[6 + j]`. See
+/// `xoodoo64_aarch64_interleave`.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline]
+unsafe fn xoodoo64_aarch64_sha3_x4_interleaved(x: &mut [uint64x2_t; 12], tracer: &mut impl Tracer) {
+    use std::arch::aarch64::*;
+
+    unsafe {
         let zero: uint64x2_t = transmute([0u8; 16]);
 
-        for &round_key in &ROUND_KEYS {
+        for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
             // theta
             let mut p0a = veor3q_u64(x[0], x[2], x[4]);
             let mut p1a = veor3q_u64(x[1], x[3], x[5]);
             let mut p0b = veor3q_u64(x[6], x[8], x[10]);
             let mut p1b = veor3q_u64(x[7], x[9], x[11]);
-            p0a = vxarq_u64::<10>(p0a, vxarq_u64::<19>(p0a, zero));
-            p1a = vxarq_u64::<10>(p1a, vxarq_u64::<19>(p1a, zero));
-            p0b = vxarq_u64::<10>(p0b, vxarq_u64::<19>(p0b, zero));
-            p1b = vxarq_u64::<10>(p1b, vxarq_u64::<19>(p1b, zero));
+            p0a = vxarq_u64::<{ 64 - 10 }>(p0a, vxarq_u64::<{ 64 - 19 }>(p0a, zero));
+            p1a = vxarq_u64::<{ 64 - 10 }>(p1a, vxarq_u64::<{ 64 - 19 }>(p1a, zero));
+            p0b = vxarq_u64::<{ 64 - 10 }>(p0b, vxarq_u64::<{ 64 - 19 }>(p0b, zero));
+            p1b = vxarq_u64::<{ 64 - 10 }>(p1b, vxarq_u64::<{ 64 - 19 }>(p1b, zero));
             (p0a, p1a) = (p1a, p0a);
             (p0b, p1b) = (p1b, p0b);
 
@@ -1902,98 +1348,170 @@ unsafe fn xoodoo64_aarch64_sha3_x4(state: &mut [u8; 192]) {
             x[7] = veorq_u64(x[7], p1b);
             x[9] = veorq_u64(x[9], p1b);
             x[11] = veorq_u64(x[11], p1b);
+            tracer.record(round, Step::Theta, &xoodoo64_aarch64_x4_lanes(x));
 
             // rho west
             x.swap(2, 3);
             x.swap(8, 9);
-            x[4] = vxarq_u64::<23>(x[4], zero);
-            x[5] = vxarq_u64::<23>(x[5], zero);
-            x[10] = vxarq_u64::<23>(x[10], zero);
-            x[11] = vxarq_u64::<23>(x[11], zero);
+            x[4] = vxarq_u64::<{ 64 - 23 }>(x[4], zero);
+            x[5] = vxarq_u64::<{ 64 - 23 }>(x[5], zero);
+            x[10] = vxarq_u64::<{ 64 - 23 }>(x[10], zero);
+            x[11] = vxarq_u64::<{ 64 - 23 }>(x[11], zero);
+            tracer.record(round, Step::RhoWest, &xoodoo64_aarch64_x4_lanes(x));
 
             // iota
             x[0] = veorq_u64(x[0], vdupq_n_u64(round_key as u64));
             x[6] = veorq_u64(x[6], vdupq_n_u64(round_key as u64));
+            tracer.record(round, Step::Iota, &xoodoo64_aarch64_x4_lanes(x));
 
             // chi
-            let t0 = vbcaxq_u64(x[4], x[2], x[0]);
-            let t1 = vbcaxq_u64(x[0], x[4], x[2]);
-            let t2 = vbcaxq_u64(x[2], x[0], x[4]);
+            let t0 = vbcaxq_u64(x[0], x[4], x[2]);
+            let t1 = vbcaxq_u64(x[2], x[0], x[4]);
+            let t2 = vbcaxq_u64(x[4], x[2], x[0]);
             x[0] = t0;
             x[2] = t1;
             x[4] = t2;
-            let t0 = vbcaxq_u64(x[5], x[3], x[1]);
-            let t1 = vbcaxq_u64(x[1], x[5], x[3]);
-            let t2 = vbcaxq_u64(x[3], x[1], x[5]);
+            let t0 = vbcaxq_u64(x[1], x[5], x[3]);
+            let t1 = vbcaxq_u64(x[3], x[1], x[5]);
+            let t2 = vbcaxq_u64(x[5], x[3], x[1]);
             x[1] = t0;
             x[3] = t1;
             x[5] = t2;
-            let t0 = vbcaxq_u64(x[10], x[8], x[6]);
-            let t1 = vbcaxq_u64(x[6], x[10], x[8]);
-            let t2 = vbcaxq_u64(x[8], x[6], x[10]);
+            let t0 = vbcaxq_u64(x[6], x[10], x[8]);
+            let t1 = vbcaxq_u64(x[8], x[6], x[10]);
+            let t2 = vbcaxq_u64(x[10], x[8], x[6]);
             x[6] = t0;
             x[8] = t1;
             x[10] = t2;
-            let t0 = vbcaxq_u64(x[11], x[9], x[7]);
-            let t1 = vbcaxq_u64(x[7], x[11], x[9]);
-            let t2 = vbcaxq_u64(x[9], x[7], x[11]);
+            let t0 = vbcaxq_u64(x[7], x[11], x[9]);
+            let t1 = vbcaxq_u64(x[9], x[7], x[11]);
+            let t2 = vbcaxq_u64(x[11], x[9], x[7]);
             x[7] = t0;
             x[9] = t1;
             x[11] = t2;
+            tracer.record(round, Step::Chi, &xoodoo64_aarch64_x4_lanes(x));
 
             // rho east
-            x[2] = vxarq_u64::<1>(x[2], zero);
-            x[3] = vxarq_u64::<1>(x[3], zero);
-            x[8] = vxarq_u64::<1>(x[8], zero);
-            x[9] = vxarq_u64::<1>(x[9], zero);
+            x[2] = vxarq_u64::<{ 64 - 1 }>(x[2], zero);
+            x[3] = vxarq_u64::<{ 64 - 1 }>(x[3], zero);
+            x[8] = vxarq_u64::<{ 64 - 1 }>(x[8], zero);
+            x[9] = vxarq_u64::<{ 64 - 1 }>(x[9], zero);
             x.swap(4, 5);
             x.swap(10, 11);
-            x[4] = vxarq_u64::<16>(x[4], zero);
-            x[5] = vxarq_u64::<16>(x[5], zero);
-            x[10] = vxarq_u64::<16>(x[10], zero);
-            x[11] = vxarq_u64::<16>(x[11], zero);
+            x[4] = vxarq_u64::<{ 64 - 16 }>(x[4], zero);
+            x[5] = vxarq_u64::<{ 64 - 16 }>(x[5], zero);
+            x[10] = vxarq_u64::<{ 64 - 16 }>(x[10], zero);
+            x[11] = vxarq_u64::<{ 64 - 16 }>(x[11], zero);
+            tracer.record(round, Step::RhoEast, &xoodoo64_aarch64_x4_lanes(x));
         }
+    }
+}
 
-        // Transpose back to grouped-by-block
-        x = [
-            vtrn1q_u64(x[0], x[3]),
-            vtrn1q_u64(x[1], x[4]),
-            vtrn1q_u64(x[2], x[5]),
-            vtrn2q_u64(x[0], x[3]),
-            vtrn2q_u64(x[1], x[4]),
-            vtrn2q_u64(x[2], x[5]),
-            vtrn1q_u64(x[6], x[9]),
-            vtrn1q_u64(x[7], x[10]),
-            vtrn1q_u64(x[8], x[11]),
-            vtrn2q_u64(x[6], x[9]),
-            vtrn2q_u64(x[7], x[10]),
-            vtrn2q_u64(x[8], x[11]),
-        ];
+/// `xoodoo64_permute_k_times` for four states, with the NEON backend: the states
+/// are transposed once and stay in registers between permutations.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "sha3")]
+#[inline(never)]
+unsafe fn xoodoo64_aarch64_sha3_permute_k_times(states: &mut [[u8; 48]; 4], k: usize) {
+    let (pairs, _) = states.as_chunks_mut::<2>();
+    let mut x: [_; 2] =
+        std::array::from_fn(|i| xoodoo64_aarch64_interleave(pairs[i].as_flattened()));
+    for _ in 0..k {
+        unsafe {
+            xoodoo64_aarch64_sha3_x4_interleaved(
+                x.as_flattened_mut().try_into().unwrap(),
+                &mut NoTrace,
+            )
+        };
+    }
+    for (x, pair) in x.iter().zip(pairs) {
+        xoodoo64_aarch64_deinterleave(x, pair.as_flattened_mut());
+    }
+}
 
-        // Store results back
-        write128(transmute(x[0]), &mut state[0..16]);
-        write128(transmute(x[1]), &mut state[16..32]);
-        write128(transmute(x[2]), &mut state[32..48]);
-        write128(transmute(x[3]), &mut state[48..64]);
-        write128(transmute(x[4]), &mut state[64..80]);
-        write128(transmute(x[5]), &mut state[80..96]);
-        write128(transmute(x[6]), &mut state[96..112]);
-        write128(transmute(x[7]), &mut state[112..128]);
-        write128(transmute(x[8]), &mut state[128..144]);
-        write128(transmute(x[9]), &mut state[144..160]);
-        write128(transmute(x[10]), &mut state[160..176]);
-        write128(transmute(x[11]), &mut state[176..192]);
+/// An operation that can be run against every permutation backend, whatever its
+/// state size. See `for_each_backend`.
+trait BackendVisitor {
+    fn visit<const N: usize>(
+        &mut self,
+        name: &str,
+        parallelism: usize,
+        f: impl Fn(&mut [u8; N]) + Sync,
+    );
+}
+
+/// Calls `v` with each permutation backend available on this target.
+fn for_each_backend(v: &mut impl BackendVisitor) {
+    v.visit("xoodoo_scalar", 1, xoodoo_scalar);
+    v.visit("xoodoo_scalar_x2", 2, xoodoo_scalar_x2);
+    v.visit("xoodoo_scalar_x3", 3, |s: &mut [u8; 144]| {
+        xoodoo_scalar_xn::<3>(as_states(s))
+    });
+    v.visit("xoodoo_scalar_x4", 4, xoodoo_scalar_x4);
+    v.visit("xoodoo_scalar_x6", 6, |s: &mut [u8; 288]| {
+        xoodoo_scalar_xn::<6>(as_states(s))
+    });
+    v.visit("xoodoo_scalar_x8", 8, |s: &mut [u8; 384]| {
+        xoodoo_scalar_xn::<8>(as_states(s))
+    });
+    v.visit("xoodoo64_scalar", 1, xoodoo64_scalar);
+    v.visit("xoodoo64_scalar_lfsr", 1, xoodoo64_scalar_lfsr);
+    v.visit("xoodoo64_scalar_x2", 2, xoodoo64_scalar_x2);
+    v.visit("xoodoo64_scalar_x3", 3, |s: &mut [u8; 144]| {
+        xoodoo64_scalar_xn::<3>(as_states(s))
+    });
+    v.visit("xoodoo64_scalar_x4", 4, xoodoo64_scalar_x4);
+    v.visit("xoodoo64_scalar_x6", 6, |s: &mut [u8; 288]| {
+        xoodoo64_scalar_xn::<6>(as_states(s))
+    });
+    v.visit("xoodoo64_scalar_x8", 8, |s: &mut [u8; 384]| {
+        xoodoo64_scalar_xn::<8>(as_states(s))
+    });
+    v.visit("xoodoo64_bitinterleaved", 1, xoodoo64_bitinterleaved);
+    #[cfg(any(feature = "portable", test))]
+    {
+        v.visit("xoodoo_portable", 1, portable::xoodoo);
+        v.visit("xoodoo_portable_x4", 4, |s: &mut [u8; 192]| {
+            portable::xoodoo_x4(as_states(s))
+        });
+        v.visit("xoodoo64_portable", 1, portable::xoodoo64);
+        v.visit("xoodoo64_portable_x2", 2, |s: &mut [u8; 96]| {
+            portable::xoodoo64_x2(as_states(s))
+        });
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        v.visit("xoodoo_neon", 1, xoodoo_aarch64);
+        v.visit("xoodoo_neon_x2", 2, xoodoo_aarch64_x2);
+        v.visit("xoodoo_neon_x4", 4, xoodoo_aarch64_x4);
+        v.visit("xoodoo_neon_sha3", 1, |x| unsafe { xoodoo_aarch64_sha3(x) });
+        v.visit("xoodoo_neon_sha3_x2", 2, |x| unsafe {
+            xoodoo_aarch64_sha3_x2(x)
+        });
+        v.visit("xoodoo_neon_sha3_x4", 4, |x| unsafe {
+            xoodoo_aarch64_sha3_x4(x)
+        });
+        v.visit("xoodoo64_neon_sha3", 1, |x| unsafe {
+            xoodoo64_aarch64_sha3(x)
+        });
+        v.visit("xoodoo64_neon_sha3_x2", 2, |x| unsafe {
+            xoodoo64_aarch64_sha3_x2(x)
+        });
+        v.visit("xoodoo64_neon_sha3_x4", 4, |x| unsafe {
+            xoodoo64_aarch64_sha3_x4(x)
+        });
     }
 }
 
 const CLOCK_FREQ: f64 = 3.5e9; // According to Wikipedia
 
+const ITERS: usize = 10_000_000;
+
+/// Latency benchmark. Run rounds consecutively, with dependencies between rounds.
+///
+/// An approximation of single-stream Xoodyak; see `duplex` for a closer model.
 #[inline(never)]
-fn benchmark<const N: usize>(name: &str, parallelism: usize, f: impl Fn(&mut [u8; N])) {
-    const ITERS: usize = 10_000_000;
-    // Latency benchmark. Run rounds consecutively, with dependencies between rounds.
-    //
-    // An approximation of single-stream Xoodyak.
+fn latency<const N: usize>(f: &impl Fn(&mut [u8; N])) -> f64 {
     let mut x = black_box([0u8; N]);
     let start = Instant::now();
     for _ in 0..ITERS {
@@ -2001,8 +1519,21 @@ fn benchmark<const N: usize>(name: &str, parallelism: usize, f: impl Fn(&mut [u8
     }
     black_box(x);
     let elapsed = start.elapsed();
-    let latency = elapsed.as_nanos() as f64 / ITERS as f64;
+    elapsed.as_nanos() as f64 / ITERS as f64
+}
+
+#[inline(never)]
+fn benchmark<const N: usize>(
+    perf: Option<&perf::Counters>,
+    name: &str,
+    parallelism: usize,
+    f: impl Fn(&mut [u8; N]),
+) {
+    let latency = latency(&f);
     // Throughput benchmark. Run many independent rounds.
+    if let Some(perf) = perf {
+        perf.start();
+    }
     let start = Instant::now();
     for _ in 0..ITERS {
         let mut x = black_box([0u8; N]);
@@ -2010,43 +1541,138 @@ fn benchmark<const N: usize>(name: &str, parallelism: usize, f: impl Fn(&mut [u8
         black_box(&x);
     }
     let elapsed = start.elapsed();
+    let sample = perf.map(|perf| perf.stop());
     let throughput = elapsed.as_nanos() as f64 / (parallelism * ITERS) as f64;
     let throughput_cpb = (elapsed.as_secs_f64() * CLOCK_FREQ) / (parallelism * ITERS * 48) as f64;
-    println!(
+    let mut row = format_row(name, latency, parallelism, throughput, throughput_cpb);
+    if let Some(sample) = sample {
+        row += &format_perf_columns(&sample, parallelism * ITERS);
+    }
+    println!("{row}");
+}
+
+struct Benchmark<'a> {
+    perf: Option<&'a perf::Counters>,
+}
+
+impl BackendVisitor for Benchmark<'_> {
+    fn visit<const N: usize>(
+        &mut self,
+        name: &str,
+        parallelism: usize,
+        f: impl Fn(&mut [u8; N]) + Sync,
+    ) {
+        benchmark(self.perf, name, parallelism, f);
+    }
+}
+
+fn format_header() -> String {
+    format!(
+        "{:<30} {:>16}  {:>4} {:>16} {:>16}",
+        "name", "latency (ns)", "par", "throughput (ns)", "throughput (cpb)"
+    )
+}
+
+fn format_row(
+    name: &str,
+    latency: f64,
+    parallelism: usize,
+    throughput: f64,
+    throughput_cpb: f64,
+) -> String {
+    format!(
         "{:<30} {:>16.1} {:4}x {:16.1} {:>16.2}",
         name, latency, parallelism, throughput, throughput_cpb
-    );
+    )
 }
 
-fn main() {
+/// Counter columns, normalized per permutation. Counters cover the throughput loop.
+fn format_perf_columns(sample: &perf::Sample, permutations: usize) -> String {
+    let per_perm = |count: u64| count as f64 / permutations as f64;
+    let uops = match sample.uops {
+        Some(uops) => format!("{:.1}", per_perm(uops)),
+        None => "-".to_string(),
+    };
+    format!(
+        " {:>12.1} {:>12.1} {:>6.2} {:>12}",
+        per_perm(sample.instructions),
+        per_perm(sample.cycles),
+        sample.instructions as f64 / sample.cycles as f64,
+        uops
+    )
+}
+
+fn run_benchmarks(args: &[String]) {
+    let counters = match args {
+        [] => None,
+        [flag] if flag == "--perf" => {
+            let counters = perf::Counters::open();
+            if counters.is_none() {
+                eprintln!("perf counters unavailable, continuing without them");
+            }
+            counters
+        }
+        _ => usage(),
+    };
+    let perf = counters.as_ref();
+
     println!("Assuming CPU frequency is {:.1} GHz", CLOCK_FREQ / 1e9);
-    println!(
-        "{:<30} {:>16}  {:>4} {:>16} {:>16}",
-        "name", "latency (ns)", "par", "throughput (ns)", "throughput (cpb)"
+    let mut header = format_header();
+    if perf.is_some() {
+        header += &format!(
+            " {:>12} {:>12} {:>6} {:>12}",
+            "instr/perm", "cycles/perm", "IPC", "uops/perm"
+        );
+    }
+    println!("{header}");
+    for_each_backend(&mut Benchmark { perf });
+}
+
+fn usage() -> ! {
+    eprintln!("usage: xoodoo64 [bench [--perf]]");
+    eprintln!("       xoodoo64 duplex");
+    eprintln!("       xoodoo64 fused");
+    eprintln!("       xoodoo64 bitsliced");
+    eprintln!("       xoodoo64 sweep [hash|deck]");
+    eprintln!("       xoodoo64 threads [N]");
+    eprintln!(
+        "       xoodoo64 trails [differential|linear] [rounds] [pivot_columns] [expand_columns] [max_weight]"
     );
-    benchmark("xoodoo_scalar", 1, xoodoo_scalar);
-    benchmark("xoodoo_scalar_x2", 2, xoodoo_scalar_x2);
-    benchmark("xoodoo_scalar_x4", 4, xoodoo_scalar_x4);
-    benchmark("xoodoo64_scalar", 1, xoodoo64_scalar);
-    benchmark("xoodoo64_scalar_x2", 2, xoodoo64_scalar_x2);
-    benchmark("xoodoo64_scalar_x4", 4, xoodoo64_scalar_x4);
-    benchmark("xoodoo_neon", 1, xoodoo_aarch64);
-    benchmark("xoodoo_neon_x2", 2, xoodoo_aarch64_x2);
-    benchmark("xoodoo_neon_x4", 4, xoodoo_aarch64_x4);
-    benchmark("xoodoo_neon_sha3", 1, |x| unsafe { xoodoo_aarch64_sha3(x) });
-    benchmark("xoodoo_neon_sha3_x2", 2, |x| unsafe {
-        xoodoo_aarch64_sha3_x2(x)
-    });
-    benchmark("xoodoo_neon_sha3_x4", 4, |x| unsafe {
-        xoodoo_aarch64_sha3_x4(x)
-    });
-    benchmark("xoodoo64_neon_sha3", 1, |x| unsafe {
-        xoodoo64_aarch64_sha3(x)
-    });
-    benchmark("xoodoo64_neon_sha3_x2", 2, |x| unsafe {
-        xoodoo64_aarch64_sha3_x2(x)
-    });
-    benchmark("xoodoo64_neon_sha3_x4", 4, |x| unsafe {
-        xoodoo64_aarch64_sha3_x4(x)
-    });
+    eprintln!("       xoodoo64 kernel [odd_columns] [kernel_columns]");
+    eprintln!("       xoodoo64 offsets [samples] [finalists] [seed]");
+    eprintln!("       xoodoo64 diffusion [max_rounds] [samples]");
+    eprintln!("       xoodoo64 cube [max_rounds] [max_dim] [trials]");
+    eprintln!("       xoodoo64 symmetry [max_rounds] [widened|lfsr|c0,c1,...]");
+    eprintln!(
+        "       xoodoo64 export <cnf|anf> <xoodoo|xoodoo64> <rounds> [--constants <schedule>]"
+    );
+    eprintln!("                       [--in <bits>=<hex>]... [--out <bits>=<hex>]...");
+    eprintln!("       xoodoo64 check");
+    eprintln!("       xoodoo64 trace <backend> [other_backend [seed]]");
+    eprintln!("       xoodoo64 readme <results.txt> [README.md]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => run_benchmarks(&[]),
+        Some("bench") => run_benchmarks(&args[1..]),
+        Some("duplex") => duplex::main(&args[1..]),
+        Some("fused") => fused::main(&args[1..]),
+        Some("bitsliced") => bitsliced::main(&args[1..]),
+        Some("sweep") => constructions::main(&args[1..]),
+        Some("threads") => threads::main(&args[1..]),
+        Some("trails") => trails::main(&args[1..]),
+        Some("kernel") => kernel::main(&args[1..]),
+        Some("offsets") => offsets::main(&args[1..]),
+        Some("diffusion") => diffusion::main(&args[1..]),
+        Some("cube") => cube::main(&args[1..]),
+        Some("symmetry") => symmetry::main(&args[1..]),
+        Some("export") => export::main(&args[1..]),
+        Some("check") => check::main(&args[1..]),
+        Some("trace") => trace::main(&args[1..]),
+        Some("readme") => readme::main(&args[1..]),
+        Some(_) => usage(),
+    }
 }
@@ -0,0 +1,210 @@
+//! Search for alternative theta and rho offsets for the 64-bit layout.
+//!
+//! The offsets of `xoodoo64_scalar` reuse or double Xoodoo's. Candidates are
+//! restricted to forms that stay cheap in SIMD: every rotation is in 1..63, so
+//! that it is a single `vxarq_u64` (or a shift pair without SHA3), and the
+//! rho-east rotation of plane 2 is a multiple of 8 so that it folds into the
+//! byte shuffle that already implements the lane shift. Lane shifts are fixed,
+//! since with two lanes the only nontrivial shift is 1.
+//!
+//! Ranking is staged, cheapest metric first:
+//!
+//! 1. theta rotation pairs are kept if they have the fewest parity patterns
+//!    without theta-effect and the best column branch number over two odd
+//!    columns;
+//! 2. random rho offsets are drawn for the kept pairs and ranked by the number
+//!    of rounds to full diffusion, then by the number of bits reached after two
+//!    rounds;
+//! 3. the best of those are ranked by their lightest 4-round differential and
+//!    linear trails.
+
+use crate::kernel::{effect_free_dimension, effect_stats};
+use crate::trails::{Kind, Search};
+use crate::variant::{Rng, Variant, XOODOO64, ZERO, parse_args};
+
+/// Trail search parameters for the finalists; see `trails`.
+const TRAIL_ROUNDS: usize = 4;
+const PIVOT_COLUMNS: usize = 2;
+const EXPAND_COLUMNS: u32 = 8;
+const MAX_WEIGHT: u32 = 80;
+
+const MAX_DIFFUSION_ROUNDS: usize = 12;
+
+/// The column branch number of theta over parity patterns of one or two odd
+/// columns.
+fn branch(v: &Variant) -> u32 {
+    effect_stats(v, 1)
+        .min_branch
+        .min(effect_stats(v, 2).min_branch)
+}
+
+/// The offsets a candidate is chosen by.
+fn offsets(v: &Variant) -> ([u32; 2], u32, u32, u32) {
+    (
+        v.theta_rot,
+        v.rho_west_rot,
+        v.rho_east_rot1,
+        v.rho_east_rot2,
+    )
+}
+
+struct Candidate {
+    variant: Variant,
+    effect_free: usize,
+    branch: u32,
+    diffusion: Option<usize>,
+    /// The fewest bits that may depend on one input bit after two rounds.
+    density: u32,
+    trails: [Option<u32>; 2],
+}
+
+impl Candidate {
+    fn new(variant: Variant, effect_free: usize, branch: u32) -> Candidate {
+        let density = (0..3)
+            .map(|y| {
+                let mut a = ZERO;
+                a[y][0] = 1;
+                variant.spread(&mut a);
+                variant.spread(&mut a);
+                variant.num_active_bits(&a)
+            })
+            .min()
+            .unwrap();
+        Candidate {
+            variant,
+            effect_free,
+            branch,
+            diffusion: variant.full_diffusion_rounds(MAX_DIFFUSION_ROUNDS),
+            density,
+            trails: [None; 2],
+        }
+    }
+
+    fn search_trails(&mut self) {
+        for (i, kind) in [Kind::Differential, Kind::Linear].into_iter().enumerate() {
+            let search = Search::new(kind, &self.variant, EXPAND_COLUMNS);
+            self.trails[i] = search
+                .best(TRAIL_ROUNDS, PIVOT_COLUMNS, MAX_WEIGHT)
+                .map(|t| t.weight);
+        }
+    }
+
+    /// The weight of the lightest trail of either kind (unknown trails count as
+    /// heavier than anything found).
+    fn trail_weight(&self) -> u32 {
+        self.trails
+            .iter()
+            .map(|w| w.unwrap_or(MAX_WEIGHT + 1))
+            .min()
+            .unwrap()
+    }
+
+    fn print(&self, note: &str) {
+        let v = &self.variant;
+        let trail = |w: Option<u32>| w.map_or(format!(">{MAX_WEIGHT}"), |w| w.to_string());
+        let diffusion = self
+            .diffusion
+            .map_or(format!(">{MAX_DIFFUSION_ROUNDS}"), |r| r.to_string());
+        println!(
+            "{:>8} {:>9} {:>9} {:>7} {:>7} {:>10} {:>11} {:>8} {:>8}  {}",
+            format!("{}/{}", v.theta_rot[0], v.theta_rot[1]),
+            v.rho_west_rot,
+            format!("{}/{}", v.rho_east_rot1, v.rho_east_rot2),
+            format!("2^{}", self.effect_free),
+            self.branch,
+            diffusion,
+            self.density,
+            trail(self.trails[0]),
+            trail(self.trails[1]),
+            note
+        );
+    }
+}
+
+pub fn main(args: &[String]) {
+    let [samples, finalists, seed] = parse_args(args, [2000, 16, 1]);
+    let seed = seed as u64;
+
+    let bits = XOODOO64.lane_bits;
+    let mut pairs = Vec::new();
+    for r0 in 1..bits {
+        for r1 in r0 + 1..bits {
+            let v = Variant {
+                theta_rot: [r0, r1],
+                ..XOODOO64
+            };
+            pairs.push((v.theta_rot, effect_free_dimension(&v), branch(&v)));
+        }
+    }
+    let best_dim = pairs.iter().map(|p| p.1).min().unwrap();
+    let best_branch = pairs
+        .iter()
+        .filter(|p| p.1 == best_dim)
+        .map(|p| p.2)
+        .max()
+        .unwrap();
+    let total_pairs = pairs.len();
+    pairs.retain(|p| p.1 == best_dim && p.2 == best_branch);
+    println!(
+        "{total_pairs} theta rotation pairs, {} with 2^{best_dim} parity patterns without theta-effect \
+         and column branch number {best_branch}",
+        pairs.len()
+    );
+
+    let mut rng = Rng(seed);
+    let mut candidates: Vec<Candidate> = (0..samples)
+        .map(|_| {
+            let (theta_rot, effect_free, branch) = pairs[rng.below(pairs.len() as u64) as usize];
+            let variant = Variant {
+                name: "candidate",
+                theta_rot,
+                rho_west_rot: 1 + rng.below(bits as u64 - 1) as u32,
+                rho_east_rot1: 1 + rng.below(bits as u64 - 1) as u32,
+                rho_east_rot2: 8 * (1 + rng.below(bits as u64 / 8 - 1) as u32),
+                ..XOODOO64
+            };
+            Candidate::new(variant, effect_free, branch)
+        })
+        .collect();
+    candidates.sort_by_key(|c| offsets(&c.variant));
+    candidates.dedup_by_key(|c| offsets(&c.variant));
+    candidates.sort_by_key(|c| (c.diffusion.unwrap_or(usize::MAX), u32::MAX - c.density));
+    candidates.truncate(finalists);
+    for c in &mut candidates {
+        c.search_trails();
+    }
+    candidates.sort_by_key(|c| {
+        (
+            u32::MAX - c.trail_weight(),
+            c.diffusion.unwrap_or(usize::MAX),
+            u32::MAX - c.density,
+        )
+    });
+
+    println!(
+        "{samples} random candidates (seed {seed}); the {} that diffuse fastest, by {TRAIL_ROUNDS}-round trail weight:",
+        candidates.len()
+    );
+    println!(
+        "{:>8} {:>9} {:>9} {:>7} {:>7} {:>10} {:>11} {:>8} {:>8}",
+        "theta",
+        "rho-west",
+        "rho-east",
+        "no-eff",
+        "branch",
+        "diffusion",
+        "2r density",
+        "diff",
+        "linear"
+    );
+    let mut current = Candidate::new(
+        XOODOO64,
+        effect_free_dimension(&XOODOO64),
+        branch(&XOODOO64),
+    );
+    current.search_trails();
+    current.print("(xoodoo64_scalar)");
+    for c in &candidates {
+        c.print("");
+    }
+}
@@ -0,0 +1,163 @@
+//! Hardware performance counters via the Linux `perf_event_open` syscall.
+//!
+//! Counters are opened as a single group (cycles as the leader) so that they are
+//! scheduled onto the PMU together and their ratios are meaningful.
+
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::raw::{c_int, c_long, c_ulong};
+
+unsafe extern "C" {
+    fn syscall(number: c_long, ...) -> c_long;
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const SYS_PERF_EVENT_OPEN: Option<c_long> = Some(298);
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const SYS_PERF_EVENT_OPEN: Option<c_long> = Some(241);
+#[cfg(all(target_os = "linux", target_arch = "x86"))]
+const SYS_PERF_EVENT_OPEN: Option<c_long> = Some(336);
+#[cfg(all(target_os = "linux", target_arch = "arm"))]
+const SYS_PERF_EVENT_OPEN: Option<c_long> = Some(364);
+#[cfg(not(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "x86",
+        target_arch = "arm"
+    )
+)))]
+const SYS_PERF_EVENT_OPEN: Option<c_long> = None;
+
+const PERF_TYPE_HARDWARE: u32 = 0;
+#[cfg(target_arch = "aarch64")]
+const PERF_TYPE_RAW: u32 = 4;
+const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+/// ARMv8 PMU common event INST_SPEC: operations speculatively executed, the
+/// closest architectural proxy for uops.
+#[cfg(target_arch = "aarch64")]
+const ARMV8_INST_SPEC: u64 = 0x1b;
+
+const PERF_FORMAT_GROUP: u64 = 1 << 3;
+const ATTR_DISABLED: u64 = 1 << 0;
+const ATTR_EXCLUDE_KERNEL: u64 = 1 << 5;
+const ATTR_EXCLUDE_HV: u64 = 1 << 6;
+
+const PERF_EVENT_IOC_ENABLE: c_ulong = 0x2400;
+const PERF_EVENT_IOC_DISABLE: c_ulong = 0x2401;
+const PERF_EVENT_IOC_RESET: c_ulong = 0x2403;
+const PERF_IOC_FLAG_GROUP: c_ulong = 1;
+
+/// `struct perf_event_attr`, truncated to `PERF_ATTR_SIZE_VER0`.
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    type_: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+fn open(type_: u32, config: u64, group: Option<&File>) -> Option<File> {
+    let attr = PerfEventAttr {
+        type_,
+        size: size_of::<PerfEventAttr>() as u32,
+        config,
+        read_format: PERF_FORMAT_GROUP,
+        flags: if group.is_none() { ATTR_DISABLED } else { 0 }
+            | ATTR_EXCLUDE_KERNEL
+            | ATTR_EXCLUDE_HV,
+        ..Default::default()
+    };
+    let group_fd = group.map_or(-1, |g| g.as_raw_fd());
+    // pid = 0, cpu = -1: this thread, on whichever CPU it runs.
+    let fd = unsafe {
+        syscall(
+            SYS_PERF_EVENT_OPEN?,
+            &attr as *const PerfEventAttr,
+            0 as c_int,
+            -1 as c_int,
+            group_fd as c_int,
+            0 as c_ulong,
+        )
+    };
+    if fd < 0 {
+        return None;
+    }
+    Some(unsafe { File::from_raw_fd(fd as c_int) })
+}
+
+/// Counter values accumulated between `Counters::start` and `Counters::stop`.
+pub struct Sample {
+    pub cycles: u64,
+    pub instructions: u64,
+    pub uops: Option<u64>,
+}
+
+pub struct Counters {
+    leader: File,
+    // Group members only need to stay open; they are read through the leader.
+    _members: Vec<File>,
+    has_uops: bool,
+}
+
+impl Counters {
+    /// Opens the counters for the calling thread, or returns `None` if the kernel
+    /// refuses (no PMU, or `perf_event_paranoid` too strict).
+    pub fn open() -> Option<Counters> {
+        let leader = open(PERF_TYPE_HARDWARE, PERF_COUNT_HW_CPU_CYCLES, None)?;
+        #[allow(unused_mut)]
+        let mut members = vec![open(
+            PERF_TYPE_HARDWARE,
+            PERF_COUNT_HW_INSTRUCTIONS,
+            Some(&leader),
+        )?];
+        #[cfg(target_arch = "aarch64")]
+        members.extend(open(PERF_TYPE_RAW, ARMV8_INST_SPEC, Some(&leader)));
+        let has_uops = members.len() > 1;
+        Some(Counters {
+            leader,
+            _members: members,
+            has_uops,
+        })
+    }
+
+    fn ioctl(&self, request: c_ulong) {
+        unsafe { ioctl(self.leader.as_raw_fd(), request, PERF_IOC_FLAG_GROUP) };
+    }
+
+    pub fn start(&self) {
+        self.ioctl(PERF_EVENT_IOC_RESET);
+        self.ioctl(PERF_EVENT_IOC_ENABLE);
+    }
+
+    pub fn stop(&self) -> Sample {
+        self.ioctl(PERF_EVENT_IOC_DISABLE);
+        // PERF_FORMAT_GROUP layout: nr, then one value per counter in open order.
+        let mut buf = [0u8; 8 * 4];
+        let n = (&self.leader).read(&mut buf).unwrap_or(0);
+        let values: Vec<u64> = buf[..n]
+            .chunks_exact(8)
+            .map(|c| u64::from_ne_bytes(c.try_into().unwrap()))
+            .collect();
+        Sample {
+            cycles: values.get(1).copied().unwrap_or(0),
+            instructions: values.get(2).copied().unwrap_or(0),
+            uops: if self.has_uops {
+                values.get(3).copied()
+            } else {
+                None
+            },
+        }
+    }
+}
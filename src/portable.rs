@@ -0,0 +1,312 @@
+//! Backends written once against a small vector abstraction, instead of one
+//! target's intrinsics. Enabled by the `portable` feature.
+//!
+//! `U32x4` and `U64x2` are 16-byte vectors with the operations the NEON
+//! backends use: XOR, AND-NOT, rotation of every word, and a byte shuffle by a
+//! constant table. Each operation is a loop over the words or bytes of an
+//! aligned array, which LLVM turns into the target's vector instructions (NEON,
+//! SSE, AVX2), so a new ISA only needs the compiler to support it.
+//!
+//! The single-instance backends keep a plane per vector, like
+//! `xoodoo_aarch64` and `xoodoo64_aarch64_sha3`, with the same shuffle tables
+//! from `shuffles`. The `_x4`/`_x2` backends keep lane `j` of every instance in
+//! one vector, the layout of `scalar::xoodoo_interleave`.
+//!
+//! Each backend has a `_traced` version that reports the state after every step
+//! to a `Tracer`, as the scalar backends do.
+
+use crate::constants::WIDENED;
+use crate::shuffles::{SHIFT_LANE, SWAP_LANES, XOODOO_RHO_EAST, XOODOO64_RHO_EAST};
+use crate::trace::{NoTrace, Step, Tracer};
+use crate::{ROUND_KEYS, scalar};
+
+macro_rules! vector {
+    ($(#[$attr:meta])* $name:ident, $word:ty, $words:literal) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(C, align(16))]
+        pub struct $name(pub [$word; $words]);
+
+        impl $name {
+            const WORD_BYTES: usize = size_of::<$word>();
+
+            /// A vector of `w` in the first word and zeros elsewhere.
+            #[inline(always)]
+            pub fn first(w: $word) -> $name {
+                $name(std::array::from_fn(|i| if i == 0 { w } else { 0 }))
+            }
+
+            #[inline(always)]
+            pub fn splat(w: $word) -> $name {
+                $name([w; $words])
+            }
+
+            #[inline(always)]
+            pub fn from_bytes(bytes: [u8; 16]) -> $name {
+                $name(std::array::from_fn(|i| {
+                    let word = &bytes[Self::WORD_BYTES * i..Self::WORD_BYTES * (i + 1)];
+                    <$word>::from_le_bytes(word.try_into().unwrap())
+                }))
+            }
+
+            #[inline(always)]
+            pub fn to_bytes(self) -> [u8; 16] {
+                let mut bytes = [0u8; 16];
+                for (chunk, w) in bytes.chunks_exact_mut(Self::WORD_BYTES).zip(self.0) {
+                    chunk.copy_from_slice(&w.to_le_bytes());
+                }
+                bytes
+            }
+
+            #[inline(always)]
+            pub fn load(bytes: &[u8]) -> $name {
+                $name::from_bytes(bytes[..16].try_into().unwrap())
+            }
+
+            #[inline(always)]
+            pub fn store(self, bytes: &mut [u8]) {
+                bytes[..16].copy_from_slice(&self.to_bytes());
+            }
+
+            #[inline(always)]
+            pub fn xor(self, other: $name) -> $name {
+                $name(std::array::from_fn(|i| self.0[i] ^ other.0[i]))
+            }
+
+            /// `self & !other`, as NEON's `vbic`.
+            #[inline(always)]
+            pub fn andnot(self, other: $name) -> $name {
+                $name(std::array::from_fn(|i| self.0[i] & !other.0[i]))
+            }
+
+            #[inline(always)]
+            pub fn rotate_left<const R: u32>(self) -> $name {
+                $name(std::array::from_fn(|i| self.0[i].rotate_left(R)))
+            }
+
+            /// Byte `i` of the result is byte `table[i]` of `self`, as NEON's
+            /// `vqtbl1q_u8` and SSSE3's `pshufb`.
+            #[inline(always)]
+            pub fn shuffle_bytes(self, table: [u8; 16]) -> $name {
+                let bytes = self.to_bytes();
+                $name::from_bytes(std::array::from_fn(|i| bytes[table[i] as usize % 16]))
+            }
+
+            /// The lanes of a state kept a plane per vector, grouped by word as
+            /// a `Tracer` takes them.
+            #[inline(always)]
+            pub fn lanes(planes: [$name; 3]) -> [[$word; 1]; 3 * $words] {
+                std::array::from_fn(|j| [planes[j / $words].0[j % $words]])
+            }
+        }
+    };
+}
+
+vector!(
+    /// Four 32-bit words.
+    U32x4, u32, 4
+);
+vector!(
+    /// Two 64-bit words.
+    U64x2, u64, 2
+);
+
+/// Xoodoo, one plane per vector.
+#[inline(never)]
+pub fn xoodoo(x: &mut [u8; 48]) {
+    xoodoo_traced(x, &mut NoTrace);
+}
+
+/// `xoodoo`, recording the state after every step.
+#[inline(always)]
+pub fn xoodoo_traced(x: &mut [u8; 48], tracer: &mut impl Tracer) {
+    let mut a = U32x4::load(&x[0..16]);
+    let mut b = U32x4::load(&x[16..32]);
+    let mut c = U32x4::load(&x[32..48]);
+
+    for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
+        // theta
+        let p = a.xor(b).xor(c).shuffle_bytes(SHIFT_LANE);
+        let e = p.rotate_left::<5>().xor(p.rotate_left::<14>());
+        a = a.xor(e);
+        b = b.xor(e);
+        c = c.xor(e);
+        tracer.record(round, Step::Theta, &U32x4::lanes([a, b, c]));
+
+        // rho west
+        b = b.shuffle_bytes(SHIFT_LANE);
+        c = c.rotate_left::<11>();
+        tracer.record(round, Step::RhoWest, &U32x4::lanes([a, b, c]));
+
+        // iota
+        a = a.xor(U32x4::first(round_key));
+        tracer.record(round, Step::Iota, &U32x4::lanes([a, b, c]));
+
+        // chi
+        (a, b, c) = (a.xor(c.andnot(b)), b.xor(a.andnot(c)), c.xor(b.andnot(a)));
+        tracer.record(round, Step::Chi, &U32x4::lanes([a, b, c]));
+
+        // rho east
+        b = b.rotate_left::<1>();
+        c = c.shuffle_bytes(XOODOO_RHO_EAST);
+        tracer.record(round, Step::RhoEast, &U32x4::lanes([a, b, c]));
+    }
+
+    a.store(&mut x[0..16]);
+    b.store(&mut x[16..32]);
+    c.store(&mut x[32..48]);
+}
+
+/// Xoodoo64, one plane per vector.
+#[inline(never)]
+pub fn xoodoo64(x: &mut [u8; 48]) {
+    xoodoo64_traced(x, &mut NoTrace);
+}
+
+/// `xoodoo64`, recording the state after every step.
+#[inline(always)]
+pub fn xoodoo64_traced(x: &mut [u8; 48], tracer: &mut impl Tracer) {
+    let mut a = U64x2::load(&x[0..16]);
+    let mut b = U64x2::load(&x[16..32]);
+    let mut c = U64x2::load(&x[32..48]);
+
+    for (round, &round_key) in WIDENED.iter().enumerate() {
+        // theta
+        let p = a.xor(b).xor(c).shuffle_bytes(SWAP_LANES);
+        let e = p.rotate_left::<10>().xor(p.rotate_left::<29>());
+        a = a.xor(e);
+        b = b.xor(e);
+        c = c.xor(e);
+        tracer.record(round, Step::Theta, &U64x2::lanes([a, b, c]));
+
+        // rho west
+        b = b.shuffle_bytes(SWAP_LANES);
+        c = c.rotate_left::<23>();
+        tracer.record(round, Step::RhoWest, &U64x2::lanes([a, b, c]));
+
+        // iota
+        a = a.xor(U64x2::first(round_key));
+        tracer.record(round, Step::Iota, &U64x2::lanes([a, b, c]));
+
+        // chi
+        (a, b, c) = (a.xor(c.andnot(b)), b.xor(a.andnot(c)), c.xor(b.andnot(a)));
+        tracer.record(round, Step::Chi, &U64x2::lanes([a, b, c]));
+
+        // rho east
+        b = b.rotate_left::<1>();
+        c = c.shuffle_bytes(XOODOO64_RHO_EAST);
+        tracer.record(round, Step::RhoEast, &U64x2::lanes([a, b, c]));
+    }
+
+    a.store(&mut x[0..16]);
+    b.store(&mut x[16..32]);
+    c.store(&mut x[32..48]);
+}
+
+/// Four instances of Xoodoo, lane `j` of each in `x[j]`.
+#[inline(never)]
+pub fn xoodoo_x4(states: &mut [[u8; 48]; 4]) {
+    xoodoo_x4_traced(states, &mut NoTrace);
+}
+
+/// `xoodoo_x4`, recording the state after every step.
+#[inline(always)]
+pub fn xoodoo_x4_traced(states: &mut [[u8; 48]; 4], tracer: &mut impl Tracer) {
+    let mut x = scalar::xoodoo_interleave(states).map(U32x4);
+
+    for (round, &round_key) in ROUND_KEYS.iter().enumerate() {
+        // theta
+        let p: [U32x4; 4] = std::array::from_fn(|l| x[l].xor(x[4 + l]).xor(x[8 + l]));
+        for l in 0..4 {
+            let p = p[(l + 3) % 4];
+            let e = p.rotate_left::<5>().xor(p.rotate_left::<14>());
+            x[l] = x[l].xor(e);
+            x[4 + l] = x[4 + l].xor(e);
+            x[8 + l] = x[8 + l].xor(e);
+        }
+        tracer.record(round, Step::Theta, &x.map(|v| v.0));
+
+        // rho west
+        let b: [U32x4; 4] = std::array::from_fn(|l| x[4 + (l + 3) % 4]);
+        for l in 0..4 {
+            x[4 + l] = b[l];
+            x[8 + l] = x[8 + l].rotate_left::<11>();
+        }
+        tracer.record(round, Step::RhoWest, &x.map(|v| v.0));
+
+        // iota
+        x[0] = x[0].xor(U32x4::splat(round_key));
+        tracer.record(round, Step::Iota, &x.map(|v| v.0));
+
+        // chi
+        for l in 0..4 {
+            let (a, b, c) = (x[l], x[4 + l], x[8 + l]);
+            x[l] = a.xor(c.andnot(b));
+            x[4 + l] = b.xor(a.andnot(c));
+            x[8 + l] = c.xor(b.andnot(a));
+        }
+        tracer.record(round, Step::Chi, &x.map(|v| v.0));
+
+        // rho east
+        let c: [U32x4; 4] = std::array::from_fn(|l| x[8 + (l + 2) % 4]);
+        for l in 0..4 {
+            x[4 + l] = x[4 + l].rotate_left::<1>();
+            x[8 + l] = c[l].rotate_left::<8>();
+        }
+        tracer.record(round, Step::RhoEast, &x.map(|v| v.0));
+    }
+
+    scalar::xoodoo_deinterleave(&x.map(|v| v.0), states);
+}
+
+/// Two instances of Xoodoo64, lane `j` of each in `x[j]`.
+#[inline(never)]
+pub fn xoodoo64_x2(states: &mut [[u8; 48]; 2]) {
+    xoodoo64_x2_traced(states, &mut NoTrace);
+}
+
+/// `xoodoo64_x2`, recording the state after every step.
+#[inline(always)]
+pub fn xoodoo64_x2_traced(states: &mut [[u8; 48]; 2], tracer: &mut impl Tracer) {
+    let mut x = scalar::xoodoo64_interleave(states).map(U64x2);
+
+    for (round, &round_key) in WIDENED.iter().enumerate() {
+        // theta
+        let p: [U64x2; 2] = std::array::from_fn(|l| x[l].xor(x[2 + l]).xor(x[4 + l]));
+        for l in 0..2 {
+            let p = p[(l + 1) % 2];
+            let e = p.rotate_left::<10>().xor(p.rotate_left::<29>());
+            x[l] = x[l].xor(e);
+            x[2 + l] = x[2 + l].xor(e);
+            x[4 + l] = x[4 + l].xor(e);
+        }
+        tracer.record(round, Step::Theta, &x.map(|v| v.0));
+
+        // rho west
+        (x[2], x[3]) = (x[3], x[2]);
+        x[4] = x[4].rotate_left::<23>();
+        x[5] = x[5].rotate_left::<23>();
+        tracer.record(round, Step::RhoWest, &x.map(|v| v.0));
+
+        // iota
+        x[0] = x[0].xor(U64x2::splat(round_key));
+        tracer.record(round, Step::Iota, &x.map(|v| v.0));
+
+        // chi
+        for l in 0..2 {
+            let (a, b, c) = (x[l], x[2 + l], x[4 + l]);
+            x[l] = a.xor(c.andnot(b));
+            x[2 + l] = b.xor(a.andnot(c));
+            x[4 + l] = c.xor(b.andnot(a));
+        }
+        tracer.record(round, Step::Chi, &x.map(|v| v.0));
+
+        // rho east
+        x[2] = x[2].rotate_left::<1>();
+        x[3] = x[3].rotate_left::<1>();
+        (x[4], x[5]) = (x[5].rotate_left::<16>(), x[4].rotate_left::<16>());
+        tracer.record(round, Step::RhoEast, &x.map(|v| v.0));
+    }
+
+    scalar::xoodoo64_deinterleave(&x.map(|v| v.0), states);
+}
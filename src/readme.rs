@@ -0,0 +1,266 @@
+//! Regenerates the results sections of README.md from saved benchmark output.
+//!
+//! The sections are delimited by `<!-- BEGIN name -->` / `<!-- END name -->`
+//! markers, so the prose around them is left alone.
+
+use crate::{format_header, format_row};
+
+struct Row {
+    name: String,
+    latency: f64,
+    parallelism: usize,
+    throughput: f64,
+    throughput_cpb: f64,
+}
+
+impl Row {
+    /// Parses a row as printed by `benchmark`, ignoring any trailing columns.
+    fn parse(line: &str) -> Option<Row> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 5 {
+            return None;
+        }
+        Some(Row {
+            name: fields[0].to_string(),
+            latency: fields[1].parse().ok()?,
+            parallelism: fields[2].strip_suffix('x')?.parse().ok()?,
+            throughput: fields[3].parse().ok()?,
+            throughput_cpb: fields[4].parse().ok()?,
+        })
+    }
+
+    /// The permutation, e.g. `xoodoo64` for `xoodoo64_neon_sha3_x2`.
+    fn permutation(&self) -> &str {
+        self.name.split('_').next().unwrap()
+    }
+
+    /// The benchmark name without its parallelism suffix, e.g. `xoodoo64_neon_sha3`.
+    fn family(&self) -> &str {
+        let name = self.name.as_str();
+        match name.rsplit_once("_x") {
+            Some((family, n)) if n.parse::<usize>().is_ok() => family,
+            _ => name,
+        }
+    }
+}
+
+/// Picks the best result of each family according to `key` (lower is better),
+/// sorted from worst to best, as in the README tables.
+fn best_per_family(results: &[Row], key: impl Fn(&Row) -> f64) -> Vec<&Row> {
+    let mut best: Vec<&Row> = Vec::new();
+    for r in results {
+        match best.iter_mut().find(|b| b.family() == r.family()) {
+            Some(b) if key(r) < key(b) => *b = r,
+            Some(_) => {}
+            None => best.push(r),
+        }
+    }
+    best.sort_by(|a, b| key(b).total_cmp(&key(a)));
+    best
+}
+
+/// The ratio of the best 32b result to the best 64b result.
+fn ratio(best: &[&Row], key: impl Fn(&Row) -> f64) -> Option<f64> {
+    let best_of = |permutation: &str| {
+        best.iter()
+            .filter(|r| r.permutation() == permutation)
+            .map(|r| key(r))
+            .min_by(f64::total_cmp)
+    };
+    Some(best_of("xoodoo")? / best_of("xoodoo64")?)
+}
+
+fn throughput_section(results: &[Row]) -> String {
+    let key = |r: &Row| r.throughput_cpb;
+    let best = best_per_family(results, key);
+    let mut out = String::from("```\n");
+    out += &format!("{:<30}{:>16}\n", "name", "throughput (cpb)");
+    for r in &best {
+        out += &format!("{:<30}{:>16.2}\n", r.name, r.throughput_cpb);
+    }
+    out += "```\n";
+    if let Some(ratio) = ratio(&best, key) {
+        out += &format!("\nThe 64b variant is {ratio:.2}x the throughput of the 32b variant.\n");
+    }
+    out
+}
+
+fn latency_section(results: &[Row]) -> String {
+    let key = |r: &Row| r.latency;
+    let best = best_per_family(results, key);
+    let mut out = String::from("```\n");
+    out += &format!("{:<30} {:>16}\n", "name", "latency (ns)");
+    for r in &best {
+        out += &format!("{:<30} {:>16.1}\n", r.name, r.latency);
+    }
+    out += "```\n";
+    if let Some(ratio) = ratio(&best, key) {
+        out += &format!("\nThe 64b variant is {ratio:.2}x lower latency than the 32b variant.\n");
+    }
+    out
+}
+
+fn all_section(preamble: &[&str], results: &[Row]) -> String {
+    let mut out = String::from("```\n");
+    for line in preamble {
+        out += line;
+        out += "\n";
+    }
+    out += &format_header();
+    out += "\n";
+    for r in results {
+        out += &format_row(
+            &r.name,
+            r.latency,
+            r.parallelism,
+            r.throughput,
+            r.throughput_cpb,
+        );
+        out += "\n";
+    }
+    out += "```\n";
+    out
+}
+
+/// Replaces everything between the `name` markers in `readme` with `contents`.
+/// Each marker must appear exactly once, `BEGIN` before `END`.
+fn replace_section(readme: &str, name: &str, contents: &str) -> Result<String, String> {
+    let begin = format!("<!-- BEGIN {name} -->\n");
+    let end = format!("<!-- END {name} -->");
+    let find_once = |marker: &str| {
+        let mut found = readme.match_indices(marker).map(|(i, _)| i);
+        match (found.next(), found.next()) {
+            (Some(i), None) => Ok(i),
+            (None, _) => Err(format!("README is missing `{}`", marker.trim_end())),
+            (Some(_), Some(_)) => Err(format!("README has more than one `{}`", marker.trim_end())),
+        }
+    };
+    let start = find_once(&begin)? + begin.len();
+    let stop = find_once(&end)?;
+    if stop < start {
+        return Err(format!("README has `{end}` before `{}`", begin.trim_end()));
+    }
+    Ok(format!(
+        "{}{}{}",
+        &readme[..start],
+        contents,
+        &readme[stop..]
+    ))
+}
+
+pub fn main(args: &[String]) {
+    let (results_path, readme_path) = match args {
+        [results] => (results.as_str(), "README.md"),
+        [results, readme] => (results.as_str(), readme.as_str()),
+        _ => crate::usage(),
+    };
+    let input = std::fs::read_to_string(results_path).unwrap_or_else(|e| {
+        eprintln!("cannot read {results_path}: {e}");
+        std::process::exit(1);
+    });
+    let preamble: Vec<&str> = input
+        .lines()
+        .filter(|line| line.starts_with("Assuming"))
+        .collect();
+    let results: Vec<Row> = input.lines().filter_map(Row::parse).collect();
+    if results.is_empty() {
+        eprintln!("no benchmark results found in {results_path}");
+        std::process::exit(1);
+    }
+
+    let mut readme = std::fs::read_to_string(readme_path).unwrap_or_else(|e| {
+        eprintln!("cannot read {readme_path}: {e}");
+        std::process::exit(1);
+    });
+    for (name, contents) in [
+        ("throughput", throughput_section(&results)),
+        ("latency", latency_section(&results)),
+        ("all", all_section(&preamble, &results)),
+    ] {
+        readme = replace_section(&readme, name, &contents).unwrap_or_else(|e| {
+            eprintln!("{e}; {readme_path} was not changed");
+            std::process::exit(1);
+        });
+    }
+    std::fs::write(readme_path, readme).unwrap_or_else(|e| {
+        eprintln!("cannot write {readme_path}: {e}");
+        std::process::exit(1);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str) -> Row {
+        Row {
+            name: name.to_string(),
+            latency: 0.0,
+            parallelism: 1,
+            throughput: 0.0,
+            throughput_cpb: 0.0,
+        }
+    }
+
+    #[test]
+    fn parse_benchmark_line() {
+        let r = Row::parse(
+            "xoodoo64_scalar_x2                         71.4    2x             34.4             2.51",
+        )
+        .unwrap();
+        assert_eq!(r.name, "xoodoo64_scalar_x2");
+        assert_eq!(r.latency, 71.4);
+        assert_eq!(r.parallelism, 2);
+        assert_eq!(r.throughput, 34.4);
+        assert_eq!(r.throughput_cpb, 2.51);
+    }
+
+    #[test]
+    fn parse_header_line() {
+        assert!(Row::parse(&format_header()).is_none());
+    }
+
+    #[test]
+    fn family() {
+        assert_eq!(row("xoodoo64_neon_sha3_x2").family(), "xoodoo64_neon_sha3");
+        assert_eq!(row("xoodoo_lfsr").family(), "xoodoo_lfsr");
+    }
+
+    #[test]
+    fn replace_section_keeps_the_rest() {
+        let before = "# Title\r\n\n<!-- BEGIN b -->\nb\n<!-- END b -->\n\t<!-- BEGIN a -->\n";
+        let after = "<!-- END a -->\ntrailing  \n\u{e9}";
+        let readme = format!("{before}old\nlines\n{after}");
+        assert_eq!(
+            replace_section(&readme, "a", "new\n").unwrap(),
+            format!("{before}new\n{after}")
+        );
+    }
+
+    #[test]
+    fn replace_section_missing_marker() {
+        let readme = "<!-- BEGIN a -->\nold\n";
+        assert_eq!(
+            replace_section(readme, "a", "").unwrap_err(),
+            "README is missing `<!-- END a -->`"
+        );
+    }
+
+    #[test]
+    fn replace_section_duplicate_marker() {
+        let readme = "<!-- BEGIN a -->\n<!-- BEGIN a -->\nold\n<!-- END a -->\n";
+        assert_eq!(
+            replace_section(readme, "a", "").unwrap_err(),
+            "README has more than one `<!-- BEGIN a -->`"
+        );
+    }
+
+    #[test]
+    fn replace_section_end_before_begin() {
+        let readme = "<!-- END a -->\nold\n<!-- BEGIN a -->\n";
+        assert_eq!(
+            replace_section(readme, "a", "").unwrap_err(),
+            "README has `<!-- END a -->` before `<!-- BEGIN a -->`"
+        );
+    }
+}
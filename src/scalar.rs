@@ -0,0 +1,205 @@
+//! The scalar backends, generated from one description per permutation.
+//!
+//! `scalar_backends!` takes a permutation's word type, lane count and offsets,
+//! and generates functions that interleave any number `N` of instances. The
+//! state is kept grouped by word: lane `j` of every instance is stored in
+//! `x[j]`, plane by plane, which is the layout a SIMD register of `N` words
+//! would hold. Within each step the generated code loops over instances, lanes
+//! and planes; the bounds are constants after monomorphization, so the loops
+//! unroll into the same straight-line code the backends used to spell out by
+//! hand.
+//!
+//! Each step is a function on that interleaved layout, in a module per
+//! permutation, and the permutation calls them in turn; `steps` wraps them for
+//! a single state. Each description also gets `interleave` and `deinterleave`
+//! functions converting from and to the 48-byte layout of `xoodoo_scalar`, one
+//! state after the other, and a function that permutes states in that layout. Callers that permute the same states
+//! repeatedly can keep them interleaved between calls.
+//!
+//! A new parallelism level is one line in `for_each_backend`.
+
+use crate::trace::{Step, Tracer};
+
+macro_rules! scalar_backends {
+    (
+        word: $word:ty,
+        lanes: $lanes:literal,
+        read: $read:path,
+        write: $write:path,
+        theta: ($theta0:literal, $theta1:literal),
+        rho_west: $rho_west:literal,
+        rho_east: ($rho_east1:literal, $rho_east_shift:literal, $rho_east2:literal),
+        $(#[$state_attr:meta])*
+        state: $state:ident,
+        interleave: $interleave:ident,
+        deinterleave: $deinterleave:ident,
+        $(#[$steps_attr:meta])*
+        steps: $steps:ident,
+        $(#[$interleaved_attr:meta])*
+        fn $interleaved:ident;
+        $(#[$attr:meta])*
+        fn $name:ident;
+    ) => {
+        $(#[$state_attr])*
+        pub type $state<const N: usize> = [[$word; N]; 3 * $lanes];
+
+        #[inline(always)]
+        pub fn $interleave<const N: usize>(states: &[[u8; 48]; N]) -> $state<N> {
+            const LANE_BYTES: usize = size_of::<$word>();
+            std::array::from_fn(|j| {
+                std::array::from_fn(|i| $read(&states[i][LANE_BYTES * j..LANE_BYTES * (j + 1)]))
+            })
+        }
+
+        #[inline(always)]
+        pub fn $deinterleave<const N: usize>(x: &$state<N>, states: &mut [[u8; 48]; N]) {
+            const LANE_BYTES: usize = size_of::<$word>();
+            for (j, lane) in x.iter().enumerate() {
+                for (i, state) in states.iter_mut().enumerate() {
+                    $write(lane[i], &mut state[LANE_BYTES * j..LANE_BYTES * (j + 1)]);
+                }
+            }
+        }
+
+        $(#[$steps_attr])*
+        pub mod $steps {
+            use super::$state;
+
+            const L: usize = $lanes;
+
+            #[inline(always)]
+            pub fn theta<const N: usize>(x: &mut $state<N>) {
+                for i in 0..N {
+                    let p: [$word; L] =
+                        std::array::from_fn(|l| x[l][i] ^ x[L + l][i] ^ x[2 * L + l][i]);
+                    for l in 0..L {
+                        let p = p[(l + L - 1) % L];
+                        let e = p.rotate_left($theta0) ^ p.rotate_left($theta1);
+                        x[l][i] ^= e;
+                        x[L + l][i] ^= e;
+                        x[2 * L + l][i] ^= e;
+                    }
+                }
+            }
+
+            #[inline(always)]
+            pub fn rho_west<const N: usize>(x: &mut $state<N>) {
+                for i in 0..N {
+                    let plane: [$word; L] = std::array::from_fn(|l| x[L + l][i]);
+                    for l in 0..L {
+                        x[L + l][i] = plane[(l + L - 1) % L];
+                        x[2 * L + l][i] = x[2 * L + l][i].rotate_left($rho_west);
+                    }
+                }
+            }
+
+            #[inline(always)]
+            pub fn iota<const N: usize>(x: &mut $state<N>, round_key: $word) {
+                for i in 0..N {
+                    x[0][i] ^= round_key;
+                }
+            }
+
+            #[inline(always)]
+            pub fn chi<const N: usize>(x: &mut $state<N>) {
+                for i in 0..N {
+                    for l in 0..L {
+                        let (a0, a1, a2) = (x[l][i], x[L + l][i], x[2 * L + l][i]);
+                        x[l][i] = a0 ^ (!a1 & a2);
+                        x[L + l][i] = a1 ^ (!a2 & a0);
+                        x[2 * L + l][i] = a2 ^ (!a0 & a1);
+                    }
+                }
+            }
+
+            #[inline(always)]
+            pub fn rho_east<const N: usize>(x: &mut $state<N>) {
+                for i in 0..N {
+                    let plane: [$word; L] = std::array::from_fn(|l| x[2 * L + l][i]);
+                    for l in 0..L {
+                        x[L + l][i] = x[L + l][i].rotate_left($rho_east1);
+                        x[2 * L + l][i] =
+                            plane[(l + L - $rho_east_shift) % L].rotate_left($rho_east2);
+                    }
+                }
+            }
+        }
+
+        $(#[$interleaved_attr])*
+        #[inline(always)]
+        pub fn $interleaved<const N: usize>(
+            x: &mut $state<N>,
+            round_keys: &[$word],
+            tracer: &mut impl Tracer,
+        ) {
+            for (round, &round_key) in round_keys.iter().enumerate() {
+                $steps::theta(x);
+                tracer.record(round, Step::Theta, x);
+                $steps::rho_west(x);
+                tracer.record(round, Step::RhoWest, x);
+                $steps::iota(x, round_key);
+                tracer.record(round, Step::Iota, x);
+                $steps::chi(x);
+                tracer.record(round, Step::Chi, x);
+                $steps::rho_east(x);
+                tracer.record(round, Step::RhoEast, x);
+            }
+        }
+
+        $(#[$attr])*
+        #[inline(always)]
+        pub fn $name<const N: usize>(
+            states: &mut [[u8; 48]; N],
+            round_keys: &[$word],
+            tracer: &mut impl Tracer,
+        ) {
+            let mut x = $interleave(states);
+            $interleaved(&mut x, round_keys, tracer);
+            $deinterleave(&x, states);
+        }
+    };
+}
+
+scalar_backends! {
+    word: u32,
+    lanes: 4,
+    read: crate::read32,
+    write: crate::write32,
+    theta: (5, 14),
+    rho_west: 11,
+    rho_east: (1, 2, 8),
+    /// `N` Xoodoo states grouped by word.
+    state: XoodooInterleaved,
+    interleave: xoodoo_interleave,
+    deinterleave: xoodoo_deinterleave,
+    /// The steps of Xoodoo on `N` interleaved states.
+    steps: xoodoo_steps,
+    /// Xoodoo on `N` interleaved states, one round per key, recording the state
+    /// after every step.
+    fn xoodoo_interleaved_traced;
+    /// `N` instances of Xoodoo, one round per key, recording the state after
+    /// every step.
+    fn xoodoo_scalar_traced;
+}
+
+scalar_backends! {
+    word: u64,
+    lanes: 2,
+    read: crate::read64,
+    write: crate::write64,
+    theta: (10, 29),
+    rho_west: 23,
+    rho_east: (1, 1, 16),
+    /// `N` Xoodoo64 states grouped by word.
+    state: Xoodoo64Interleaved,
+    interleave: xoodoo64_interleave,
+    deinterleave: xoodoo64_deinterleave,
+    /// The steps of Xoodoo64 on `N` interleaved states.
+    steps: xoodoo64_steps,
+    /// Xoodoo64 on `N` interleaved states, one round per key, recording the
+    /// state after every step.
+    fn xoodoo64_interleaved_traced;
+    /// `N` instances of Xoodoo64, one round per key, recording the state after
+    /// every step.
+    fn xoodoo64_scalar_traced;
+}
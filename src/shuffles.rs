@@ -0,0 +1,378 @@
+//! The byte tables of the vector backends' shuffles: `vqtbl1q_u8` in the NEON
+//! backends and `shuffle_bytes` in `portable`. Byte `i` of the result is byte
+//! `TABLE[i]` of the input, whose words are little-endian.
+//!
+//! The NEON backends only build on aarch64. The tests here run their rounds,
+//! with these tables and the same transposes and register moves, on
+//! `portable`'s vectors, and compare them with the scalar backends on any
+//! target.
+
+/// Moves each 32-bit lane to the next one.
+pub const SHIFT_LANE: [u8; 16] = [12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+/// Moves each 32-bit lane two lanes on and rotates it left by 8 bits.
+pub const XOODOO_RHO_EAST: [u8; 16] = [11, 8, 9, 10, 15, 12, 13, 14, 3, 0, 1, 2, 7, 4, 5, 6];
+
+/// Rotates each 32-bit word left by 8 bits.
+#[cfg(any(target_arch = "aarch64", test))]
+pub const ROTATE_WORDS_8: [u8; 16] = [3, 0, 1, 2, 7, 4, 5, 6, 11, 8, 9, 10, 15, 12, 13, 14];
+
+/// Swaps the two 64-bit lanes.
+pub const SWAP_LANES: [u8; 16] = [8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7];
+
+/// Swaps the two 64-bit lanes and rotates them left by 16 bits.
+pub const XOODOO64_RHO_EAST: [u8; 16] = [14, 15, 8, 9, 10, 11, 12, 13, 6, 7, 0, 1, 2, 3, 4, 5];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::portable::{U32x4, U64x2};
+    use crate::variant::Rng;
+    use crate::{ROUND_KEYS, xoodoo_scalar, xoodoo64_scalar};
+
+    type Bytes = [u8; 16];
+
+    /// Byte `i` of the result is byte `table[i]` of `a` followed by `b`, as
+    /// NEON's `vqtbl2q_u8`.
+    fn shuffle2(a: Bytes, b: Bytes, table: Bytes) -> Bytes {
+        std::array::from_fn(|i| match table[i] as usize {
+            t @ 0..16 => a[t],
+            t => b[t - 16],
+        })
+    }
+
+    /// `vtrn1q_u32`, `vtrn2q_u32`, `vtrn1q_u64` and `vtrn2q_u64` as tables for
+    /// `shuffle2`.
+    const TRN1_32: Bytes = [0, 1, 2, 3, 16, 17, 18, 19, 8, 9, 10, 11, 24, 25, 26, 27];
+    const TRN2_32: Bytes = [4, 5, 6, 7, 20, 21, 22, 23, 12, 13, 14, 15, 28, 29, 30, 31];
+    const TRN1_64: Bytes = [0, 1, 2, 3, 4, 5, 6, 7, 16, 17, 18, 19, 20, 21, 22, 23];
+    const TRN2_64: Bytes = [8, 9, 10, 11, 12, 13, 14, 15, 24, 25, 26, 27, 28, 29, 30, 31];
+
+    /// Vector `i` of the result is `shuffle2(x[a], x[b], table)` for entry `i`
+    /// of `moves`.
+    fn transpose<const N: usize>(x: &[Bytes], moves: [(usize, usize, Bytes); N]) -> [Bytes; N] {
+        moves.map(|(a, b, table)| shuffle2(x[a], x[b], table))
+    }
+
+    fn load<const N: usize>(bytes: &[u8]) -> [Bytes; N] {
+        std::array::from_fn(|i| bytes[16 * i..16 * i + 16].try_into().unwrap())
+    }
+
+    fn store(x: &[Bytes], bytes: &mut [u8]) {
+        bytes[..16 * x.len()].copy_from_slice(x.as_flattened());
+    }
+
+    /// `xoodoo_aarch64` and `xoodoo_aarch64_sha3`; `_x2` runs it on both
+    /// states.
+    fn xoodoo_neon(x: &mut [u8; 48]) {
+        let [mut a, mut b, mut c] = load::<3>(x).map(U32x4::from_bytes);
+        for &round_key in &ROUND_KEYS {
+            // theta
+            let p = a.xor(b).xor(c).shuffle_bytes(SHIFT_LANE);
+            let e = p.rotate_left::<5>().xor(p.rotate_left::<14>());
+            (a, b, c) = (a.xor(e), b.xor(e), c.xor(e));
+
+            // rho west
+            b = b.shuffle_bytes(SHIFT_LANE);
+            c = c.rotate_left::<11>();
+
+            // iota
+            a = a.xor(U32x4::first(round_key));
+
+            // chi, with `vbcaxq_u32(a, c, b)` as `a ^ (c & !b)`
+            (a, b, c) = (a.xor(c.andnot(b)), b.xor(a.andnot(c)), c.xor(b.andnot(a)));
+
+            // rho east
+            b = b.rotate_left::<1>();
+            c = c.shuffle_bytes(XOODOO_RHO_EAST);
+        }
+        store(&[a, b, c].map(U32x4::to_bytes), x);
+    }
+
+    /// `xoodoo_aarch64_x4` and `xoodoo_aarch64_sha3_x4`.
+    fn xoodoo_neon_x4(state: &mut [u8; 192]) {
+        let x = load::<12>(state);
+        let x = transpose(
+            &x,
+            [
+                (0, 6, TRN1_64),
+                (1, 7, TRN1_64),
+                (2, 8, TRN1_64),
+                (3, 9, TRN1_64),
+                (4, 10, TRN1_64),
+                (5, 11, TRN1_64),
+                (0, 6, TRN2_64),
+                (1, 7, TRN2_64),
+                (2, 8, TRN2_64),
+                (3, 9, TRN2_64),
+                (4, 10, TRN2_64),
+                (5, 11, TRN2_64),
+            ],
+        );
+        let x = transpose(
+            &x,
+            [
+                (0, 3, TRN1_32),
+                (0, 3, TRN2_32),
+                (6, 9, TRN1_32),
+                (6, 9, TRN2_32),
+                (1, 4, TRN1_32),
+                (1, 4, TRN2_32),
+                (7, 10, TRN1_32),
+                (7, 10, TRN2_32),
+                (2, 5, TRN1_32),
+                (2, 5, TRN2_32),
+                (8, 11, TRN1_32),
+                (8, 11, TRN2_32),
+            ],
+        );
+        let mut x = x.map(U32x4::from_bytes);
+
+        for &round_key in &ROUND_KEYS {
+            // theta
+            let p: [U32x4; 4] = std::array::from_fn(|l| x[l].xor(x[4 + l]).xor(x[8 + l]));
+            let e = p.map(|p| p.rotate_left::<5>().xor(p.rotate_left::<14>()));
+            let e = [e[3], e[0], e[1], e[2]];
+            for l in 0..4 {
+                x[l] = x[l].xor(e[l]);
+                x[4 + l] = x[4 + l].xor(e[l]);
+                x[8 + l] = x[8 + l].xor(e[l]);
+            }
+
+            // rho west
+            (x[4], x[5], x[6], x[7]) = (x[7], x[4], x[5], x[6]);
+            for c in &mut x[8..] {
+                *c = c.rotate_left::<11>();
+            }
+
+            // iota
+            x[0] = x[0].xor(U32x4::splat(round_key));
+
+            // chi
+            for l in 0..4 {
+                let (a, b, c) = (x[l], x[4 + l], x[8 + l]);
+                x[l] = a.xor(c.andnot(b));
+                x[4 + l] = b.xor(a.andnot(c));
+                x[8 + l] = c.xor(b.andnot(a));
+            }
+
+            // rho east
+            for b in &mut x[4..8] {
+                *b = b.rotate_left::<1>();
+            }
+            (x[8], x[9], x[10], x[11]) = (x[10], x[11], x[8], x[9]);
+            for c in &mut x[8..] {
+                *c = c.shuffle_bytes(ROTATE_WORDS_8);
+            }
+        }
+
+        let x = transpose(
+            &x.map(U32x4::to_bytes),
+            [
+                (0, 1, TRN1_32),
+                (4, 5, TRN1_32),
+                (8, 9, TRN1_32),
+                (0, 1, TRN2_32),
+                (4, 5, TRN2_32),
+                (8, 9, TRN2_32),
+                (2, 3, TRN1_32),
+                (6, 7, TRN1_32),
+                (10, 11, TRN1_32),
+                (2, 3, TRN2_32),
+                (6, 7, TRN2_32),
+                (10, 11, TRN2_32),
+            ],
+        );
+        let x = transpose(
+            &x,
+            [
+                (0, 6, TRN1_64),
+                (1, 7, TRN1_64),
+                (2, 8, TRN1_64),
+                (3, 9, TRN1_64),
+                (4, 10, TRN1_64),
+                (5, 11, TRN1_64),
+                (0, 6, TRN2_64),
+                (1, 7, TRN2_64),
+                (2, 8, TRN2_64),
+                (3, 9, TRN2_64),
+                (4, 10, TRN2_64),
+                (5, 11, TRN2_64),
+            ],
+        );
+        store(&x, state);
+    }
+
+    /// `xoodoo64_aarch64_sha3`.
+    fn xoodoo64_neon(x: &mut [u8; 48]) {
+        let [mut a, mut b, mut c] = load::<3>(x).map(U64x2::from_bytes);
+        for &round_key in &ROUND_KEYS {
+            // theta, with `vxarq_u64` rotating right: (p ^ p <<< 19) <<< 10
+            let p = a.xor(b).xor(c).shuffle_bytes(SWAP_LANES);
+            let e = p.xor(p.rotate_left::<19>()).rotate_left::<10>();
+            (a, b, c) = (a.xor(e), b.xor(e), c.xor(e));
+
+            // rho west
+            b = b.shuffle_bytes(SWAP_LANES);
+            c = c.rotate_left::<23>();
+
+            // iota
+            a = a.xor(U64x2::first(round_key as u64));
+
+            // chi
+            (a, b, c) = (a.xor(c.andnot(b)), b.xor(a.andnot(c)), c.xor(b.andnot(a)));
+
+            // rho east
+            b = b.rotate_left::<1>();
+            c = c.shuffle_bytes(XOODOO64_RHO_EAST);
+        }
+        store(&[a, b, c].map(U64x2::to_bytes), x);
+    }
+
+    /// `xoodoo64_aarch64_interleave`.
+    fn xoodoo64_interleave(states: &[u8]) -> [U64x2; 6] {
+        let x = transpose(
+            &load::<6>(states),
+            [
+                (0, 3, TRN1_64),
+                (0, 3, TRN2_64),
+                (1, 4, TRN1_64),
+                (1, 4, TRN2_64),
+                (2, 5, TRN1_64),
+                (2, 5, TRN2_64),
+            ],
+        );
+        x.map(U64x2::from_bytes)
+    }
+
+    /// `xoodoo64_aarch64_deinterleave`.
+    fn xoodoo64_deinterleave(x: &[U64x2; 6], states: &mut [u8]) {
+        let x = transpose(
+            &x.map(U64x2::to_bytes),
+            [
+                (0, 1, TRN1_64),
+                (2, 3, TRN1_64),
+                (4, 5, TRN1_64),
+                (0, 1, TRN2_64),
+                (2, 3, TRN2_64),
+                (4, 5, TRN2_64),
+            ],
+        );
+        store(&x, states);
+    }
+
+    /// The rounds of `xoodoo64_aarch64_sha3_x2` on every six vectors, as
+    /// `xoodoo64_aarch64_sha3_x4_interleaved` runs them on `x[..6]` and
+    /// `x[6..]`.
+    fn xoodoo64_neon_interleaved(x: &mut [U64x2]) {
+        for &round_key in &ROUND_KEYS {
+            for x in x.chunks_exact_mut(6) {
+                // theta
+                let p = [x[0].xor(x[2]).xor(x[4]), x[1].xor(x[3]).xor(x[5])];
+                let e = p.map(|p| p.xor(p.rotate_left::<19>()).rotate_left::<10>());
+                let e = [e[1], e[0]];
+                for l in 0..2 {
+                    x[l] = x[l].xor(e[l]);
+                    x[2 + l] = x[2 + l].xor(e[l]);
+                    x[4 + l] = x[4 + l].xor(e[l]);
+                }
+
+                // rho west
+                x.swap(2, 3);
+                x[4] = x[4].rotate_left::<23>();
+                x[5] = x[5].rotate_left::<23>();
+
+                // iota
+                x[0] = x[0].xor(U64x2::splat(round_key as u64));
+
+                // chi
+                for l in 0..2 {
+                    let (a, b, c) = (x[l], x[2 + l], x[4 + l]);
+                    x[l] = a.xor(c.andnot(b));
+                    x[2 + l] = b.xor(a.andnot(c));
+                    x[4 + l] = c.xor(b.andnot(a));
+                }
+
+                // rho east
+                x[2] = x[2].rotate_left::<1>();
+                x[3] = x[3].rotate_left::<1>();
+                x.swap(4, 5);
+                x[4] = x[4].rotate_left::<16>();
+                x[5] = x[5].rotate_left::<16>();
+            }
+        }
+    }
+
+    /// `xoodoo64_aarch64_sha3_x2`.
+    fn xoodoo64_neon_x2(state: &mut [u8; 96]) {
+        let mut x = xoodoo64_interleave(state);
+        xoodoo64_neon_interleaved(&mut x);
+        xoodoo64_deinterleave(&x, state);
+    }
+
+    /// `xoodoo64_aarch64_sha3_x4`.
+    fn xoodoo64_neon_x4(state: &mut [u8; 192]) {
+        let mut x: [[U64x2; 6]; 2] = std::array::from_fn(|i| xoodoo64_interleave(&state[96 * i..]));
+        xoodoo64_neon_interleaved(x.as_flattened_mut());
+        for (x, states) in x.iter().zip(state.chunks_exact_mut(96)) {
+            xoodoo64_deinterleave(x, states);
+        }
+    }
+
+    /// Runs `x1`, `x2` and `x4` on four random states each time and compares
+    /// them with `scalar` on each state.
+    fn check(
+        scalar: fn(&mut [u8; 48]),
+        x1: fn(&mut [u8; 48]),
+        x2: fn(&mut [u8; 96]),
+        x4: fn(&mut [u8; 192]),
+    ) {
+        let mut rng = Rng(1);
+        for _ in 0..16 {
+            let mut input = [0u8; 192];
+            rng.fill(&mut input);
+            let mut expected = input;
+            for state in expected.chunks_exact_mut(48) {
+                scalar(state.try_into().unwrap());
+            }
+
+            let mut x = input;
+            for state in x.chunks_exact_mut(48) {
+                x1(state.try_into().unwrap());
+            }
+            assert_eq!(x, expected, "x1");
+            let mut x = input;
+            for states in x.chunks_exact_mut(96) {
+                x2(states.try_into().unwrap());
+            }
+            assert_eq!(x, expected, "x2");
+            let mut x = input;
+            x4(&mut x);
+            assert_eq!(x, expected, "x4");
+        }
+    }
+
+    #[test]
+    fn xoodoo_neon_matches_scalar() {
+        check(
+            xoodoo_scalar,
+            xoodoo_neon,
+            |x| {
+                for state in x.chunks_exact_mut(48) {
+                    xoodoo_neon(state.try_into().unwrap());
+                }
+            },
+            xoodoo_neon_x4,
+        );
+    }
+
+    #[test]
+    fn xoodoo64_neon_matches_scalar() {
+        check(
+            xoodoo64_scalar,
+            xoodoo64_neon,
+            xoodoo64_neon_x2,
+            xoodoo64_neon_x4,
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! The steps of the scalar permutations as separate functions, for testing a
+//! single step or building custom round functions.
+//!
+//! States are arrays of lanes, plane by plane, loaded little-endian from the
+//! same 48 bytes the backends take. Each step runs the one `scalar` generates
+//! for the backends, on a single instance. Composing `round` over the round
+//! constants gives `xoodoo_scalar` and `xoodoo64_scalar`; `check` verifies this.
+
+macro_rules! steps {
+    (
+        $(#[$attr:meta])*
+        mod $name:ident {
+            word: $word:ty,
+            lanes: $lanes:literal,
+            read: $read:path,
+            write: $write:path,
+            steps: $steps:path,
+        }
+    ) => {
+        $(#[$attr])*
+        pub mod $name {
+            use $steps as steps;
+
+            pub type State = [$word; 3 * $lanes];
+
+            const LANE_BYTES: usize = size_of::<$word>();
+
+            pub fn load(bytes: &[u8; 48]) -> State {
+                std::array::from_fn(|i| $read(&bytes[LANE_BYTES * i..LANE_BYTES * (i + 1)]))
+            }
+
+            pub fn store(a: &State) -> [u8; 48] {
+                let mut bytes = [0u8; 48];
+                for (lane, chunk) in a.iter().zip(bytes.chunks_exact_mut(LANE_BYTES)) {
+                    $write(*lane, chunk);
+                }
+                bytes
+            }
+
+            /// Runs `f` on `a` as a single interleaved instance.
+            #[inline(always)]
+            fn single(a: &mut State, f: impl FnOnce(&mut [[$word; 1]; 3 * $lanes])) {
+                let mut x = a.map(|lane| [lane]);
+                f(&mut x);
+                *a = x.map(|[lane]| lane);
+            }
+
+            pub fn theta(a: &mut State) {
+                single(a, steps::theta);
+            }
+
+            pub fn rho_west(a: &mut State) {
+                single(a, steps::rho_west);
+            }
+
+            pub fn iota(a: &mut State, round_key: $word) {
+                single(a, |x| steps::iota(x, round_key));
+            }
+
+            pub fn chi(a: &mut State) {
+                single(a, steps::chi);
+            }
+
+            pub fn rho_east(a: &mut State) {
+                single(a, steps::rho_east);
+            }
+
+            pub fn round(a: &mut State, round_key: $word) {
+                theta(a);
+                rho_west(a);
+                iota(a, round_key);
+                chi(a);
+                rho_east(a);
+            }
+
+            /// One round per key.
+            pub fn permute(a: &mut State, round_keys: &[$word]) {
+                for &round_key in round_keys {
+                    round(a, round_key);
+                }
+            }
+        }
+    };
+}
+
+steps! {
+    /// Xoodoo: 3 planes of 4 32-bit lanes.
+    mod xoodoo {
+        word: u32,
+        lanes: 4,
+        read: crate::read32,
+        write: crate::write32,
+        steps: crate::scalar::xoodoo_steps,
+    }
+}
+
+steps! {
+    /// Xoodoo64: 3 planes of 2 64-bit lanes.
+    mod xoodoo64 {
+        word: u64,
+        lanes: 2,
+        read: crate::read64,
+        write: crate::write64,
+        steps: crate::scalar::xoodoo64_steps,
+    }
+}
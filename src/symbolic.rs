@@ -0,0 +1,218 @@
+//! Bit-level, step-by-step model of Xoodoo and Xoodoo64, generic over the bit
+//! type.
+//!
+//! Every step is written per bit from the specification, independently of the
+//! word-level code in `variant` and in the backends, so that it can cross-check
+//! both. A state is a vector of bits numbered as `Variant::bit` does, which is
+//! also bit `i % 8` of byte `i / 8` in the backends' layout. The bit type
+//! decides what is computed:
+//!
+//! * `bool` gives the permutation itself;
+//! * `Linear` tracks GF(2) affine expressions in input variables, through the
+//!   linear steps;
+//! * `Difference` tracks XOR differences through the linear steps, where round
+//!   constants cancel out.
+
+use crate::variant::Variant;
+use std::collections::BTreeSet;
+
+pub trait Bit: Clone {
+    fn constant(c: bool) -> Self;
+    fn xor(&self, other: &Self) -> Self;
+
+    fn not(&self) -> Self {
+        self.xor(&Self::constant(true))
+    }
+}
+
+/// Bits that chi can be applied to.
+pub trait Nonlinear: Bit {
+    fn and(&self, other: &Self) -> Self;
+}
+
+impl Bit for bool {
+    fn constant(c: bool) -> bool {
+        c
+    }
+
+    fn xor(&self, other: &bool) -> bool {
+        self ^ other
+    }
+}
+
+impl Nonlinear for bool {
+    fn and(&self, other: &bool) -> bool {
+        self & other
+    }
+}
+
+/// An affine expression over GF(2): the sum of `vars` and `one`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Linear {
+    pub vars: BTreeSet<usize>,
+    pub one: bool,
+}
+
+impl Linear {
+    pub fn var(i: usize) -> Linear {
+        Linear {
+            vars: BTreeSet::from([i]),
+            one: false,
+        }
+    }
+
+    /// The value of the expression when variable `i` is `values[i]`.
+    pub fn eval(&self, values: &[bool]) -> bool {
+        self.vars.iter().fold(self.one, |acc, &i| acc ^ values[i])
+    }
+}
+
+impl Bit for Linear {
+    fn constant(c: bool) -> Linear {
+        Linear {
+            vars: BTreeSet::new(),
+            one: c,
+        }
+    }
+
+    fn xor(&self, other: &Linear) -> Linear {
+        Linear {
+            vars: self
+                .vars
+                .symmetric_difference(&other.vars)
+                .copied()
+                .collect(),
+            one: self.one ^ other.one,
+        }
+    }
+}
+
+/// The XOR difference between two states.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Difference(pub bool);
+
+impl Bit for Difference {
+    fn constant(_: bool) -> Difference {
+        Difference(false)
+    }
+
+    fn xor(&self, other: &Difference) -> Difference {
+        Difference(self.0 ^ other.0)
+    }
+}
+
+/// The index of bit `z` of lane `x` of plane `y`, with `x` and `z` taken modulo
+/// the state's shape.
+fn index(v: &Variant, y: usize, x: usize, z: u32) -> usize {
+    let bits = v.lane_bits as usize;
+    (y * v.lanes + x % v.lanes) * bits + (z % v.lane_bits) as usize
+}
+
+/// Calls `f(y, x, z)` for every bit of the state.
+fn for_each_bit(v: &Variant, mut f: impl FnMut(usize, usize, u32)) {
+    for y in 0..3 {
+        for x in 0..v.lanes {
+            for z in 0..v.lane_bits {
+                f(y, x, z);
+            }
+        }
+    }
+}
+
+/// `E[x][z] = P[x - shift][z - rot0] + P[x - shift][z - rot1]`, added to every
+/// plane.
+pub fn theta<B: Bit>(v: &Variant, a: &[B]) -> Vec<B> {
+    let parity = |x: usize, z: u32| {
+        a[index(v, 0, x, z)]
+            .xor(&a[index(v, 1, x, z)])
+            .xor(&a[index(v, 2, x, z)])
+    };
+    let mut out = a.to_vec();
+    let (lanes, bits) = (v.lanes, v.lane_bits);
+    for_each_bit(v, |y, x, z| {
+        let px = x + lanes - v.theta_shift % lanes;
+        let e0 = parity(px, z + bits - v.theta_rot[0]);
+        let e = e0.xor(&parity(px, z + bits - v.theta_rot[1]));
+        out[index(v, y, x, z)] = a[index(v, y, x, z)].xor(&e);
+    });
+    out
+}
+
+/// Plane 1 moves by `rho_west_shift` lanes, plane 2 rotates by `rho_west_rot`.
+pub fn rho_west<B: Bit>(v: &Variant, a: &[B]) -> Vec<B> {
+    let mut out = a.to_vec();
+    for_each_bit(v, |y, x, z| {
+        let to = match y {
+            0 => index(v, 0, x, z),
+            1 => index(v, 1, x + v.rho_west_shift, z),
+            _ => index(v, 2, x, z + v.rho_west_rot),
+        };
+        out[to] = a[index(v, y, x, z)].clone();
+    });
+    out
+}
+
+/// Adds the round constant to lane 0 of plane 0.
+pub fn iota<B: Bit>(v: &Variant, a: &[B], round_constant: u64) -> Vec<B> {
+    let mut out = a.to_vec();
+    for z in 0..v.lane_bits {
+        if round_constant >> z & 1 == 1 {
+            let i = index(v, 0, 0, z);
+            out[i] = out[i].not();
+        }
+    }
+    out
+}
+
+/// `b[y] = a[y] + (a[y + 1] + 1) * a[y + 2]`.
+pub fn chi<B: Nonlinear>(v: &Variant, a: &[B]) -> Vec<B> {
+    let mut out = a.to_vec();
+    for_each_bit(v, |y, x, z| {
+        let a1 = &a[index(v, (y + 1) % 3, x, z)];
+        let a2 = &a[index(v, (y + 2) % 3, x, z)];
+        out[index(v, y, x, z)] = a[index(v, y, x, z)].xor(&a1.not().and(a2));
+    });
+    out
+}
+
+/// Plane 1 rotates by `rho_east_rot1`, plane 2 moves by `rho_east_shift` lanes
+/// and rotates by `rho_east_rot2`.
+pub fn rho_east<B: Bit>(v: &Variant, a: &[B]) -> Vec<B> {
+    let mut out = a.to_vec();
+    for_each_bit(v, |y, x, z| {
+        let to = match y {
+            0 => index(v, 0, x, z),
+            1 => index(v, 1, x, z + v.rho_east_rot1),
+            _ => index(v, 2, x + v.rho_east_shift, z + v.rho_east_rot2),
+        };
+        out[to] = a[index(v, y, x, z)].clone();
+    });
+    out
+}
+
+pub fn round<B: Nonlinear>(v: &Variant, a: &[B], round_constant: u64) -> Vec<B> {
+    let a = theta(v, a);
+    let a = rho_west(v, &a);
+    let a = iota(v, &a, round_constant);
+    let a = chi(v, &a);
+    rho_east(v, &a)
+}
+
+/// One round per constant.
+pub fn permute<B: Nonlinear>(v: &Variant, a: &[B], round_constants: &[u64]) -> Vec<B> {
+    round_constants
+        .iter()
+        .fold(a.to_vec(), |a, &c| round(v, &a, c))
+}
+
+pub fn from_bytes(bytes: &[u8; 48]) -> Vec<bool> {
+    (0..384).map(|i| bytes[i / 8] >> (i % 8) & 1 == 1).collect()
+}
+
+pub fn to_bytes(bits: &[bool]) -> [u8; 48] {
+    let mut bytes = [0u8; 48];
+    for (i, &b) in bits.iter().enumerate() {
+        bytes[i / 8] |= (b as u8) << (i % 8);
+    }
+    bytes
+}
@@ -0,0 +1,215 @@
+//! Multi-threaded throughput: every backend on N pinned threads at once.
+//!
+//! Running one copy per core shows whether the backends contend for resources
+//! that are shared between SMT siblings or clusters. Threads are pinned in the
+//! order the kernel lists the CPUs we are allowed to run on, and results are
+//! broken down by core type (P/E cores, or `cpu_capacity` on big.LITTLE) when
+//! the kernel exposes one.
+
+use crate::{BackendVisitor, CLOCK_FREQ, for_each_backend};
+use std::hint::black_box;
+use std::sync::Barrier;
+use std::time::{Duration, Instant};
+
+const ITERS: usize = 2_000_000;
+
+#[cfg(target_os = "linux")]
+mod affinity {
+    use std::os::raw::c_int;
+
+    /// `cpu_set_t`, which glibc and musl both size for 1024 CPUs.
+    #[repr(C)]
+    pub struct CpuSet([u64; 16]);
+
+    unsafe extern "C" {
+        fn sched_getaffinity(pid: c_int, size: usize, mask: *mut CpuSet) -> c_int;
+        fn sched_setaffinity(pid: c_int, size: usize, mask: *const CpuSet) -> c_int;
+    }
+
+    /// The CPUs this process may run on.
+    pub fn allowed_cpus() -> Option<Vec<usize>> {
+        let mut set = CpuSet([0; 16]);
+        if unsafe { sched_getaffinity(0, size_of::<CpuSet>(), &mut set) } != 0 {
+            return None;
+        }
+        Some(
+            (0..1024)
+                .filter(|&cpu| set.0[cpu / 64] >> (cpu % 64) & 1 == 1)
+                .collect(),
+        )
+    }
+
+    /// Pins the calling thread to `cpu`.
+    pub fn pin(cpu: usize) -> bool {
+        let mut set = CpuSet([0; 16]);
+        set.0[cpu / 64] |= 1 << (cpu % 64);
+        unsafe { sched_setaffinity(0, size_of::<CpuSet>(), &set) == 0 }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod affinity {
+    pub fn allowed_cpus() -> Option<Vec<usize>> {
+        None
+    }
+
+    pub fn pin(_cpu: usize) -> bool {
+        false
+    }
+}
+
+fn read_sysfs(path: &str) -> Option<String> {
+    Some(std::fs::read_to_string(path).ok()?.trim().to_string())
+}
+
+/// Parses a kernel CPU list such as `0-3,8-11`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for range in list.split(',').filter(|r| !r.is_empty()) {
+        let (lo, hi) = range.split_once('-').unwrap_or((range, range));
+        if let (Ok(lo), Ok(hi)) = (lo.parse::<usize>(), hi.parse::<usize>()) {
+            cpus.extend(lo..=hi);
+        }
+    }
+    cpus
+}
+
+/// A label for the kind of core `cpu` is, if the kernel tells us.
+fn core_type(cpu: usize) -> Option<String> {
+    // Intel hybrid parts expose one PMU per core type.
+    for (pmu, label) in [("cpu_core", "P-core"), ("cpu_atom", "E-core")] {
+        if let Some(list) = read_sysfs(&format!("/sys/devices/{pmu}/cpus"))
+            && parse_cpu_list(&list).contains(&cpu)
+        {
+            return Some(label.to_string());
+        }
+    }
+    // Arm big.LITTLE (and others) report a relative capacity per CPU.
+    let capacity = read_sysfs(&format!("/sys/devices/system/cpu/cpu{cpu}/cpu_capacity"))?;
+    Some(format!("capacity {capacity}"))
+}
+
+/// The physical core `cpu` belongs to, identified by its lowest SMT sibling.
+fn physical_core(cpu: usize) -> usize {
+    read_sysfs(&format!(
+        "/sys/devices/system/cpu/cpu{cpu}/topology/thread_siblings_list"
+    ))
+    .and_then(|list| parse_cpu_list(&list).into_iter().min())
+    .unwrap_or(cpu)
+}
+
+struct Threads {
+    cpus: Vec<usize>,
+    types: Vec<String>,
+    /// Whether a failure to pin has been reported.
+    warned: bool,
+}
+
+impl BackendVisitor for Threads {
+    fn visit<const N: usize>(
+        &mut self,
+        name: &str,
+        parallelism: usize,
+        f: impl Fn(&mut [u8; N]) + Sync,
+    ) {
+        let barrier = Barrier::new(self.cpus.len());
+        let runs: Vec<(bool, Instant, Instant)> = std::thread::scope(|s| {
+            let handles: Vec<_> = self
+                .cpus
+                .iter()
+                .map(|&cpu| {
+                    let (f, barrier) = (&f, &barrier);
+                    s.spawn(move || {
+                        let pinned = affinity::pin(cpu);
+                        barrier.wait();
+                        let start = Instant::now();
+                        for _ in 0..ITERS {
+                            let mut x = black_box([0u8; N]);
+                            f(&mut x);
+                            black_box(&x);
+                        }
+                        (pinned, start, Instant::now())
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let unpinned: Vec<usize> = runs
+            .iter()
+            .zip(&self.cpus)
+            .filter(|((pinned, ..), _)| !pinned)
+            .map(|(_, &cpu)| cpu)
+            .collect();
+        if !unpinned.is_empty() && !self.warned {
+            eprintln!("warning: could not pin threads to CPUs {unpinned:?}; they may migrate");
+            self.warned = true;
+        }
+        // From the first thread leaving the barrier to the last one finishing.
+        let first = runs.iter().map(|&(_, start, _)| start).min().unwrap();
+        let last = runs.iter().map(|&(_, _, end)| end).max().unwrap();
+        let elapsed: Vec<Duration> = runs.iter().map(|&(_, start, end)| end - start).collect();
+
+        let bytes = (parallelism * ITERS * 48) as f64;
+        let cpb = |d: &Duration| d.as_secs_f64() * CLOCK_FREQ / bytes;
+        let aggregate = cpb(&(last - first)) / self.cpus.len() as f64;
+        let per_thread = elapsed.iter().map(cpb).sum::<f64>() / elapsed.len() as f64;
+        print!("{name:<30} {parallelism:4}x {aggregate:>16.2} {per_thread:>16.2}");
+        let mut labels = self.types.clone();
+        labels.sort();
+        labels.dedup();
+        if labels.len() > 1 {
+            for label in &labels {
+                let of_type: Vec<f64> = elapsed
+                    .iter()
+                    .zip(&self.types)
+                    .filter(|(_, t)| *t == label)
+                    .map(|(d, _)| cpb(d))
+                    .collect();
+                let mean = of_type.iter().sum::<f64>() / of_type.len() as f64;
+                print!("  {label}: {mean:.2}");
+            }
+        }
+        println!();
+    }
+}
+
+pub fn main(args: &[String]) {
+    let cpus = affinity::allowed_cpus().unwrap_or_else(|| {
+        let n = std::thread::available_parallelism().map_or(1, |n| n.get());
+        (0..n).collect()
+    });
+    let threads = match args {
+        [] => cpus.len(),
+        [n] => n.parse().unwrap_or_else(|_| crate::usage()),
+        _ => crate::usage(),
+    };
+    if threads == 0 {
+        crate::usage();
+    }
+    // More threads than CPUs wrap around, oversubscribing the first CPUs.
+    let cpus: Vec<usize> = cpus.iter().copied().cycle().take(threads).collect();
+    let types: Vec<String> = cpus
+        .iter()
+        .map(|&cpu| core_type(cpu).unwrap_or_else(|| "cpu".to_string()))
+        .collect();
+    let mut cores: Vec<usize> = cpus.iter().map(|&cpu| physical_core(cpu)).collect();
+    cores.sort();
+    cores.dedup();
+
+    println!("Assuming CPU frequency is {:.1} GHz", CLOCK_FREQ / 1e9);
+    println!(
+        "{} threads on {} physical cores (CPUs {:?})",
+        cpus.len(),
+        cores.len(),
+        cpus
+    );
+    println!(
+        "{:<30} {:>5} {:>16} {:>16}",
+        "name", "par", "aggregate (cpb)", "per-thread (cpb)"
+    );
+    for_each_backend(&mut Threads {
+        cpus,
+        types,
+        warned: false,
+    });
+}
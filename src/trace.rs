@@ -0,0 +1,348 @@
+//! Round-by-round tracing of the permutation backends, and a diff of two traces.
+//!
+//! The scalar, bit-interleaved, portable and NEON backends take a `Tracer` that
+//! is handed the whole state after every step of every round; the benchmarked
+//! entry points pass `NoTrace`, whose empty `record` inlines away. `steps` is
+//! traced the same way as a reference.
+//!
+//! With one backend the trace of its first instance is printed in hex. With two,
+//! both permute the same random inputs, a multiple of the width of each, and the
+//! first differing round, step, instance, lane and bit is reported.
+
+use crate::constants::{LFSR, WIDENED};
+use crate::variant::{Rng, Variant, XOODOO, XOODOO64};
+use crate::{ROUND_KEYS, as_states};
+use crate::{scalar, steps};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Theta,
+    RhoWest,
+    Iota,
+    Chi,
+    RhoEast,
+}
+
+impl Step {
+    fn name(self) -> &'static str {
+        match self {
+            Step::Theta => "theta",
+            Step::RhoWest => "rho_west",
+            Step::Iota => "iota",
+            Step::Chi => "chi",
+            Step::RhoEast => "rho_east",
+        }
+    }
+}
+
+pub trait Lane: Copy {
+    fn extend_le(self, out: &mut Vec<u8>);
+}
+
+impl Lane for u32 {
+    fn extend_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Lane for u64 {
+    fn extend_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// Receives the state after each step of each round, grouped by word: lane `j`
+/// of instance `i` is `lanes[j][i]`.
+pub trait Tracer {
+    fn record<L: Lane, const N: usize>(&mut self, round: usize, step: Step, lanes: &[[L; N]]);
+}
+
+pub struct NoTrace;
+
+impl Tracer for NoTrace {
+    #[inline(always)]
+    fn record<L: Lane, const N: usize>(&mut self, _: usize, _: Step, _: &[[L; N]]) {}
+}
+
+/// The recorded states, as bytes in the backends' layout, one instance after
+/// the other.
+#[derive(Default)]
+pub struct Trace(pub Vec<(usize, Step, Vec<u8>)>);
+
+impl Tracer for Trace {
+    fn record<L: Lane, const N: usize>(&mut self, round: usize, step: Step, lanes: &[[L; N]]) {
+        let mut bytes = Vec::with_capacity(size_of_val(lanes));
+        for i in 0..N {
+            for lane in lanes {
+                lane[i].extend_le(&mut bytes);
+            }
+        }
+        self.0.push((round, step, bytes));
+    }
+}
+
+type Traced = fn(&mut [u8], &mut Trace);
+
+/// A step of `steps`, given the round constant.
+type StepFn<S> = fn(&mut S, u64);
+
+fn traced_steps<S>(
+    state: &mut [u8],
+    trace: &mut Trace,
+    load: fn(&[u8; 48]) -> S,
+    store: fn(&S) -> [u8; 48],
+    round_keys: &[u64],
+    steps: [(Step, StepFn<S>); 5],
+) {
+    let mut a = load((&*state).try_into().unwrap());
+    for (round, &round_key) in round_keys.iter().enumerate() {
+        for (step, f) in steps {
+            f(&mut a, round_key);
+            trace.0.push((round, step, store(&a).to_vec()));
+        }
+    }
+    state.copy_from_slice(&store(&a));
+}
+
+/// Traceable permutations: name, variant, instances and traced function.
+const TRACED: [(&str, &Variant, usize, Traced); 16] = [
+    ("xoodoo_steps", &XOODOO, 1, |s, t| {
+        use steps::xoodoo::*;
+        traced_steps(
+            s,
+            t,
+            load,
+            store,
+            &WIDENED,
+            [
+                (Step::Theta, |a, _| theta(a)),
+                (Step::RhoWest, |a, _| rho_west(a)),
+                (Step::Iota, |a, rc| iota(a, rc as u32)),
+                (Step::Chi, |a, _| chi(a)),
+                (Step::RhoEast, |a, _| rho_east(a)),
+            ],
+        )
+    }),
+    ("xoodoo_scalar", &XOODOO, 1, |s, t| {
+        scalar::xoodoo_scalar_traced(as_states::<1>(s), &ROUND_KEYS, t)
+    }),
+    ("xoodoo_scalar_x2", &XOODOO, 2, |s, t| {
+        scalar::xoodoo_scalar_traced(as_states::<2>(s), &ROUND_KEYS, t)
+    }),
+    ("xoodoo_scalar_x4", &XOODOO, 4, |s, t| {
+        scalar::xoodoo_scalar_traced(as_states::<4>(s), &ROUND_KEYS, t)
+    }),
+    ("xoodoo_scalar_x3", &XOODOO, 3, |s, t| {
+        scalar::xoodoo_scalar_traced(as_states::<3>(s), &ROUND_KEYS, t)
+    }),
+    ("xoodoo_scalar_x6", &XOODOO, 6, |s, t| {
+        scalar::xoodoo_scalar_traced(as_states::<6>(s), &ROUND_KEYS, t)
+    }),
+    ("xoodoo_scalar_x8", &XOODOO, 8, |s, t| {
+        scalar::xoodoo_scalar_traced(as_states::<8>(s), &ROUND_KEYS, t)
+    }),
+    ("xoodoo64_steps", &XOODOO64, 1, |s, t| {
+        use steps::xoodoo64::*;
+        traced_steps(
+            s,
+            t,
+            load,
+            store,
+            &WIDENED,
+            [
+                (Step::Theta, |a, _| theta(a)),
+                (Step::RhoWest, |a, _| rho_west(a)),
+                (Step::Iota, iota),
+                (Step::Chi, |a, _| chi(a)),
+                (Step::RhoEast, |a, _| rho_east(a)),
+            ],
+        )
+    }),
+    ("xoodoo64_scalar", &XOODOO64, 1, |s, t| {
+        scalar::xoodoo64_scalar_traced(as_states::<1>(s), &WIDENED, t)
+    }),
+    ("xoodoo64_scalar_lfsr", &XOODOO64, 1, |s, t| {
+        scalar::xoodoo64_scalar_traced(as_states::<1>(s), &LFSR, t)
+    }),
+    ("xoodoo64_scalar_x2", &XOODOO64, 2, |s, t| {
+        scalar::xoodoo64_scalar_traced(as_states::<2>(s), &WIDENED, t)
+    }),
+    ("xoodoo64_scalar_x4", &XOODOO64, 4, |s, t| {
+        scalar::xoodoo64_scalar_traced(as_states::<4>(s), &WIDENED, t)
+    }),
+    ("xoodoo64_scalar_x3", &XOODOO64, 3, |s, t| {
+        scalar::xoodoo64_scalar_traced(as_states::<3>(s), &WIDENED, t)
+    }),
+    ("xoodoo64_scalar_x6", &XOODOO64, 6, |s, t| {
+        scalar::xoodoo64_scalar_traced(as_states::<6>(s), &WIDENED, t)
+    }),
+    ("xoodoo64_scalar_x8", &XOODOO64, 8, |s, t| {
+        scalar::xoodoo64_scalar_traced(as_states::<8>(s), &WIDENED, t)
+    }),
+    ("xoodoo64_bitinterleaved", &XOODOO64, 1, |s, t| {
+        crate::xoodoo64_bitinterleaved_traced(
+            s.try_into().unwrap(),
+            &crate::bitinterleaved::WIDENED,
+            t,
+        )
+    }),
+];
+
+/// `48 * instances` random input bytes.
+pub(crate) fn inputs(instances: usize, seed: u64) -> Vec<u8> {
+    let mut rng = Rng(seed);
+    let mut state = vec![0u8; 48 * instances];
+    rng.fill(&mut state);
+    state
+}
+
+/// Runs `f`, which permutes `width` instances at a time, on `instances`
+/// random inputs, and returns the trace of each. `instances` must be a
+/// multiple of `width`.
+fn run(f: Traced, width: usize, instances: usize, seed: u64) -> Vec<Vec<(usize, Step, Vec<u8>)>> {
+    assert!(instances.is_multiple_of(width));
+    let mut state = inputs(instances, seed);
+    let mut traces = Vec::new();
+    for batch in state.chunks_exact_mut(48 * width) {
+        let mut trace = Trace::default();
+        f(batch, &mut trace);
+        traces.extend((0..width).map(|i| {
+            trace
+                .0
+                .iter()
+                .map(|(round, step, bytes)| (*round, *step, bytes[48 * i..48 * i + 48].to_vec()))
+                .collect()
+        }));
+    }
+    traces
+}
+
+/// The trace of the first instance of `f`, run on one batch of `width`
+/// inputs.
+pub(crate) fn trace_one(f: Traced, width: usize, seed: u64) -> Vec<(usize, Step, Vec<u8>)> {
+    run(f, width, width, seed).swap_remove(0)
+}
+
+/// `TRACED` and the vector backends available on this target.
+pub(crate) fn traceable() -> Vec<(&'static str, &'static Variant, usize, Traced)> {
+    #[allow(unused_mut)]
+    let mut traced = TRACED.to_vec();
+    #[cfg(any(feature = "portable", test))]
+    {
+        use crate::portable;
+        traced.push(("xoodoo_portable", &XOODOO, 1, |s, t| {
+            portable::xoodoo_traced(s.try_into().unwrap(), t)
+        }));
+        traced.push(("xoodoo_portable_x4", &XOODOO, 4, |s, t| {
+            portable::xoodoo_x4_traced(as_states(s), t)
+        }));
+        traced.push(("xoodoo64_portable", &XOODOO64, 1, |s, t| {
+            portable::xoodoo64_traced(s.try_into().unwrap(), t)
+        }));
+        traced.push(("xoodoo64_portable_x2", &XOODOO64, 2, |s, t| {
+            portable::xoodoo64_x2_traced(as_states(s), t)
+        }));
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        traced.push(("xoodoo_neon", &XOODOO, 1, |s, t| {
+            crate::xoodoo_aarch64_traced(s.try_into().unwrap(), t)
+        }));
+        traced.push(("xoodoo_neon_x2", &XOODOO, 2, |s, t| {
+            crate::xoodoo_aarch64_x2_traced(s.try_into().unwrap(), t)
+        }));
+        traced.push(("xoodoo_neon_x4", &XOODOO, 4, |s, t| {
+            crate::xoodoo_aarch64_x4_traced(s.try_into().unwrap(), t)
+        }));
+        if std::arch::is_aarch64_feature_detected!("sha3") {
+            traced.push(("xoodoo_neon_sha3", &XOODOO, 1, |s, t| unsafe {
+                crate::xoodoo_aarch64_sha3_traced(s.try_into().unwrap(), t)
+            }));
+            traced.push(("xoodoo_neon_sha3_x2", &XOODOO, 2, |s, t| unsafe {
+                crate::xoodoo_aarch64_sha3_x2_traced(s.try_into().unwrap(), t)
+            }));
+            traced.push(("xoodoo_neon_sha3_x4", &XOODOO, 4, |s, t| unsafe {
+                crate::xoodoo_aarch64_sha3_x4_traced(s.try_into().unwrap(), t)
+            }));
+            traced.push(("xoodoo64_neon_sha3", &XOODOO64, 1, |s, t| unsafe {
+                crate::xoodoo64_aarch64_sha3_traced(s.try_into().unwrap(), t)
+            }));
+            traced.push(("xoodoo64_neon_sha3_x2", &XOODOO64, 2, |s, t| unsafe {
+                crate::xoodoo64_aarch64_sha3_x2_traced(s.try_into().unwrap(), t)
+            }));
+            traced.push(("xoodoo64_neon_sha3_x4", &XOODOO64, 4, |s, t| unsafe {
+                crate::xoodoo64_aarch64_sha3_x4_traced(s.try_into().unwrap(), t)
+            }));
+        }
+    }
+    traced
+}
+
+fn find(name: &str) -> (&'static str, &'static Variant, usize, Traced) {
+    let traced = traceable();
+    *traced.iter().find(|(n, ..)| *n == name).unwrap_or_else(|| {
+        eprintln!("traceable permutations:");
+        for (n, ..) in &traced {
+            eprintln!("  {n}");
+        }
+        crate::usage()
+    })
+}
+
+/// The least common multiple of `a` and `b`.
+fn lcm(a: usize, b: usize) -> usize {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn main(args: &[String]) {
+    let seed = match args.get(2) {
+        Some(s) => s.parse().unwrap_or_else(|_| crate::usage()),
+        None => 1,
+    };
+    match args {
+        [a] => {
+            let (_, _, width, f) = find(a);
+            for (round, step, bytes) in &trace_one(f, width, seed) {
+                println!("{round:>2} {:<8} {}", step.name(), hex(bytes));
+            }
+        }
+        [a, b] | [a, b, _] => {
+            let (a, va, na, fa) = find(a);
+            let (b, vb, nb, fb) = find(b);
+            if va.name != vb.name {
+                eprintln!("{a} and {b} compute different permutations");
+                std::process::exit(2);
+            }
+            // Both see the same inputs, each over as many calls as it takes.
+            let instances = lcm(na, nb);
+            let (ta, tb) = (run(fa, na, instances, seed), run(fb, nb, instances, seed));
+            for s in 0..ta[0].len().min(tb[0].len()) {
+                for (instance, (ta, tb)) in ta.iter().zip(&tb).enumerate() {
+                    let ((round, step, x), (_, _, y)) = (&ta[s], &tb[s]);
+                    let Some(byte) = (0..48).find(|&i| x[i] != y[i]) else {
+                        continue;
+                    };
+                    let i = 8 * byte + (x[byte] ^ y[byte]).trailing_zeros() as usize;
+                    let (plane, lane, bit) = va.bit_position(i);
+                    println!(
+                        "{a} and {b} first differ in instance {instance} after round {round} {}: plane {plane}, lane {lane}, bit {bit}",
+                        step.name()
+                    );
+                    println!("{a:<24} {}", hex(x));
+                    println!("{b:<24} {}", hex(y));
+                    std::process::exit(1);
+                }
+            }
+            println!("{a} and {b} agree at every step of {instances} instance(s)");
+        }
+        _ => crate::usage(),
+    }
+}
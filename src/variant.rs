@@ -0,0 +1,458 @@
+//! Word-level model of the Xoodoo-like permutations, generic over their shape and
+//! step offsets, for the analysis tools.
+//!
+//! A state is three planes of `lanes` lanes of `lane_bits` bits each, stored as
+//! `[plane][lane]` in the low bits of a `u64`. Xoodoo has 4 lanes of 32 bits and
+//! Xoodoo64 has 2 lanes of 64 bits, so both have 128 columns and 384 bits.
+
+pub type State = [[u64; 4]; 3];
+
+pub const ZERO: State = [[0; 4]; 3];
+
+/// The shape and step offsets of a Xoodoo-like permutation.
+///
+/// Lane shifts move lane `x` to lane `x + shift`; rotations are to the left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Variant {
+    pub name: &'static str,
+    pub lanes: usize,
+    pub lane_bits: u32,
+    /// theta: `E[x] = (P[x - shift] <<< rot[0]) ^ (P[x - shift] <<< rot[1])`.
+    pub theta_shift: usize,
+    pub theta_rot: [u32; 2],
+    /// rho west: plane 1 is shifted by lanes, plane 2 is rotated.
+    pub rho_west_shift: usize,
+    pub rho_west_rot: u32,
+    /// rho east: plane 1 is rotated, plane 2 is shifted by lanes and rotated.
+    pub rho_east_rot1: u32,
+    pub rho_east_shift: usize,
+    pub rho_east_rot2: u32,
+}
+
+/// Xoodoo as specified.
+pub const XOODOO: Variant = Variant {
+    name: "xoodoo",
+    lanes: 4,
+    lane_bits: 32,
+    theta_shift: 1,
+    theta_rot: [5, 14],
+    rho_west_shift: 1,
+    rho_west_rot: 11,
+    rho_east_rot1: 1,
+    rho_east_shift: 2,
+    rho_east_rot2: 8,
+};
+
+/// The 64-bit variant implemented by `xoodoo64_scalar`.
+pub const XOODOO64: Variant = Variant {
+    name: "xoodoo64",
+    lanes: 2,
+    lane_bits: 64,
+    theta_shift: 1,
+    theta_rot: [10, 29],
+    rho_west_shift: 1,
+    rho_west_rot: 23,
+    rho_east_rot1: 1,
+    rho_east_shift: 1,
+    rho_east_rot2: 16,
+};
+
+pub const VARIANTS: [Variant; 2] = [XOODOO, XOODOO64];
+
+impl Variant {
+    pub fn columns(&self) -> usize {
+        self.lanes * self.lane_bits as usize
+    }
+
+    pub fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.lane_bits)
+    }
+
+    pub fn rotl(&self, w: u64, r: u32) -> u64 {
+        let r = r % self.lane_bits;
+        if r == 0 {
+            w
+        } else {
+            ((w << r) | (w >> (self.lane_bits - r))) & self.mask()
+        }
+    }
+
+    fn shift_lanes(&self, plane: [u64; 4], shift: usize) -> [u64; 4] {
+        let mut out = [0; 4];
+        for x in 0..self.lanes {
+            out[(x + shift) % self.lanes] = plane[x];
+        }
+        out
+    }
+
+    /// The column parity plane.
+    pub fn parity(&self, a: &State) -> [u64; 4] {
+        let mut p = [0; 4];
+        for x in 0..self.lanes {
+            p[x] = a[0][x] ^ a[1][x] ^ a[2][x];
+        }
+        p
+    }
+
+    /// The plane theta adds to every plane, given the column parity plane.
+    pub fn theta_effect(&self, p: &[u64; 4]) -> [u64; 4] {
+        let p = self.shift_lanes(*p, self.theta_shift);
+        let mut e = [0; 4];
+        for x in 0..self.lanes {
+            e[x] = self.rotl(p[x], self.theta_rot[0]) ^ self.rotl(p[x], self.theta_rot[1]);
+        }
+        e
+    }
+
+    pub fn theta(&self, a: &mut State) {
+        let e = self.theta_effect(&self.parity(a));
+        for plane in a.iter_mut() {
+            for x in 0..self.lanes {
+                plane[x] ^= e[x];
+            }
+        }
+    }
+
+    pub fn rho_west(&self, a: &mut State) {
+        a[1] = self.shift_lanes(a[1], self.rho_west_shift);
+        for w in &mut a[2][..self.lanes] {
+            *w = self.rotl(*w, self.rho_west_rot);
+        }
+    }
+
+    pub fn rho_east(&self, a: &mut State) {
+        for w in &mut a[1][..self.lanes] {
+            *w = self.rotl(*w, self.rho_east_rot1);
+        }
+        a[2] = self.shift_lanes(a[2], self.rho_east_shift);
+        for w in &mut a[2][..self.lanes] {
+            *w = self.rotl(*w, self.rho_east_rot2);
+        }
+    }
+
+    pub fn iota(&self, a: &mut State, round_constant: u64) {
+        a[0][0] ^= round_constant & self.mask();
+    }
+
+    pub fn chi(&self, a: &mut State) {
+        let step = |p0: [u64; 4], p1: [u64; 4], p2: [u64; 4]| -> [u64; 4] {
+            std::array::from_fn(|x| p0[x] ^ (!p1[x] & p2[x]))
+        };
+        let [a0, a1, a2] = *a;
+        *a = [step(a0, a1, a2), step(a1, a2, a0), step(a2, a0, a1)];
+    }
+
+    pub fn round(&self, a: &mut State, round_constant: u64) {
+        self.theta(a);
+        self.rho_west(a);
+        self.iota(a, round_constant);
+        self.chi(a);
+        self.rho_east(a);
+    }
+
+    /// One round per constant.
+    pub fn permute(&self, a: &mut State, round_constants: &[u64]) {
+        for &c in round_constants {
+            self.round(a, c);
+        }
+    }
+
+    /// The state moved by `dx` lanes and rotated by `dz` bits.
+    pub fn translate(&self, a: &State, dx: usize, dz: u32) -> State {
+        let mut out = ZERO;
+        for y in 0..3 {
+            for x in 0..self.lanes {
+                out[y][(x + dx) % self.lanes] = self.rotl(a[y][x], dz);
+            }
+        }
+        out
+    }
+
+    /// Reads a state stored as in the scalar backends: planes of little-endian
+    /// lanes.
+    pub fn load(&self, bytes: &[u8; 48]) -> State {
+        let lane_bytes = self.lane_bits as usize / 8;
+        let mut a = ZERO;
+        for (i, chunk) in bytes.chunks_exact(lane_bytes).enumerate() {
+            let mut word = [0u8; 8];
+            word[..lane_bytes].copy_from_slice(chunk);
+            a[i / self.lanes][i % self.lanes] = u64::from_le_bytes(word);
+        }
+        a
+    }
+
+    pub fn store(&self, a: &State) -> [u8; 48] {
+        let lane_bytes = self.lane_bits as usize / 8;
+        let mut bytes = [0u8; 48];
+        for (i, chunk) in bytes.chunks_exact_mut(lane_bytes).enumerate() {
+            chunk.copy_from_slice(&a[i / self.lanes][i % self.lanes].to_le_bytes()[..lane_bytes]);
+        }
+        bytes
+    }
+
+    /// The linear layer between two chi steps: rho east, then theta and rho west
+    /// of the next round.
+    pub fn lambda(&self, a: &mut State) {
+        self.rho_east(a);
+        self.theta(a);
+        self.rho_west(a);
+    }
+
+    /// Marks every bit that may depend on a marked bit one round later. Theta
+    /// spreads through the column parities, and chi makes each bit depend on its
+    /// whole column.
+    pub fn spread(&self, a: &mut State) {
+        let mut p = self.active_columns(a);
+        p = self.shift_lanes(p, self.theta_shift);
+        for x in 0..self.lanes {
+            let e = self.rotl(p[x], self.theta_rot[0]) | self.rotl(p[x], self.theta_rot[1]);
+            for plane in a.iter_mut() {
+                plane[x] |= e;
+            }
+        }
+        self.rho_west(a);
+        let c = self.active_columns(a);
+        for plane in a.iter_mut() {
+            *plane = c;
+        }
+        self.rho_east(a);
+    }
+
+    /// The number of rounds after which every bit may depend on every input
+    /// bit, or `None` if that takes more than `max_rounds`. All steps but iota
+    /// commute with translations, so one bit per plane is enough to check.
+    pub fn full_diffusion_rounds(&self, max_rounds: usize) -> Option<usize> {
+        let mut worst = 0;
+        for y in 0..3 {
+            let mut a = ZERO;
+            a[y][0] = 1;
+            let rounds = (1..=max_rounds).find(|_| {
+                self.spread(&mut a);
+                self.num_active_bits(&a) == self.bits() as u32
+            })?;
+            worst = worst.max(rounds);
+        }
+        Some(worst)
+    }
+
+    pub fn num_active_bits(&self, a: &State) -> u32 {
+        a.iter().flatten().map(|w| w.count_ones()).sum()
+    }
+
+    /// Bit-mask of the active columns of each lane.
+    pub fn active_columns(&self, a: &State) -> [u64; 4] {
+        let mut cols = [0; 4];
+        for x in 0..self.lanes {
+            cols[x] = a[0][x] | a[1][x] | a[2][x];
+        }
+        cols
+    }
+
+    pub fn num_active_columns(&self, a: &State) -> u32 {
+        self.active_columns(a).iter().map(|c| c.count_ones()).sum()
+    }
+
+    /// The 3-bit value of column `(x, z)`, plane 0 in bit 0.
+    pub fn column(&self, a: &State, x: usize, z: u32) -> u8 {
+        (0..3).map(|y| ((a[y][x] >> z & 1) as u8) << y).sum()
+    }
+
+    pub fn set_column(&self, a: &mut State, x: usize, z: u32, v: u8) {
+        for (y, plane) in a.iter_mut().enumerate() {
+            plane[x] = plane[x] & !(1 << z) | (((v >> y) & 1) as u64) << z;
+        }
+    }
+
+    pub fn bits(&self) -> usize {
+        3 * self.columns()
+    }
+
+    /// Bit `i` of the state, numbered plane-major, then lane, then bit.
+    pub fn bit(&self, a: &State, i: usize) -> bool {
+        let (y, x, z) = self.bit_position(i);
+        a[y][x] >> z & 1 == 1
+    }
+
+    pub fn flip_bit(&self, a: &mut State, i: usize) {
+        let (y, x, z) = self.bit_position(i);
+        a[y][x] ^= 1 << z;
+    }
+
+    pub fn bit_position(&self, i: usize) -> (usize, usize, u32) {
+        let bits = self.lane_bits as usize;
+        let lanes = self.lanes;
+        (i / (lanes * bits), i / bits % lanes, (i % bits) as u32)
+    }
+}
+
+/// A linear map on states, stored as the images of the unit vectors so that it
+/// can be applied cheaply to sparse states.
+pub struct LinearMap {
+    images: Vec<State>,
+    variant: Variant,
+}
+
+impl LinearMap {
+    pub fn new(variant: &Variant, f: impl Fn(&mut State)) -> LinearMap {
+        let images = (0..variant.bits())
+            .map(|i| {
+                let mut a = ZERO;
+                variant.flip_bit(&mut a, i);
+                f(&mut a);
+                a
+            })
+            .collect();
+        LinearMap {
+            images,
+            variant: *variant,
+        }
+    }
+
+    pub fn apply(&self, a: &State) -> State {
+        let mut out = ZERO;
+        for (y, plane) in a.iter().enumerate() {
+            for (x, &lane) in plane.iter().enumerate().take(self.variant.lanes) {
+                let mut w = lane;
+                while w != 0 {
+                    let z = w.trailing_zeros() as usize;
+                    w &= w - 1;
+                    let i = (y * self.variant.lanes + x) * self.variant.lane_bits as usize + z;
+                    xor_into(&mut out, &self.images[i]);
+                }
+            }
+        }
+        out
+    }
+
+    /// The transposed map, which propagates linear masks backwards through this
+    /// map: `u . f(a) == transpose(u) . a`.
+    pub fn transpose(&self) -> LinearMap {
+        let n = self.variant.bits();
+        let mut images = vec![ZERO; n];
+        for (j, image) in self.images.iter().enumerate() {
+            for (i, t) in images.iter_mut().enumerate() {
+                if self.variant.bit(image, i) {
+                    self.variant.flip_bit(t, j);
+                }
+            }
+        }
+        LinearMap {
+            images,
+            variant: self.variant,
+        }
+    }
+
+    /// The inverse map, by Gaussian elimination over GF(2).
+    ///
+    /// Panics if the map is not invertible.
+    pub fn inverse(&self) -> LinearMap {
+        let n = self.variant.bits();
+        let words = n.div_ceil(64);
+        // Row i pairs image i with unit vector i. Row operations keep each row's
+        // left half equal to the image of its right half, so once the left halves
+        // are reduced to unit vectors, the right halves are their preimages.
+        let pack = |a: &State| -> Vec<u64> {
+            let mut v = vec![0u64; words];
+            for j in 0..n {
+                if self.variant.bit(a, j) {
+                    v[j / 64] |= 1 << (j % 64);
+                }
+            }
+            v
+        };
+        let mut rows: Vec<(Vec<u64>, Vec<u64>)> = self
+            .images
+            .iter()
+            .enumerate()
+            .map(|(i, img)| {
+                let mut id = vec![0u64; words];
+                id[i / 64] |= 1 << (i % 64);
+                (pack(img), id)
+            })
+            .collect();
+        for col in 0..n {
+            let pivot = (col..n)
+                .find(|&r| rows[r].0[col / 64] >> (col % 64) & 1 == 1)
+                .expect("linear map is not invertible");
+            rows.swap(col, pivot);
+            let (lhs, rhs) = rows[col].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r != col && row.0[col / 64] >> (col % 64) & 1 == 1 {
+                    for w in 0..words {
+                        row.0[w] ^= lhs[w];
+                        row.1[w] ^= rhs[w];
+                    }
+                }
+            }
+        }
+        let images = rows
+            .iter()
+            .map(|(_, rhs)| {
+                let mut a = ZERO;
+                for i in 0..n {
+                    if rhs[i / 64] >> (i % 64) & 1 == 1 {
+                        self.variant.flip_bit(&mut a, i);
+                    }
+                }
+                a
+            })
+            .collect();
+        LinearMap {
+            images,
+            variant: self.variant,
+        }
+    }
+}
+
+pub fn xor_into(a: &mut State, b: &State) {
+    for y in 0..3 {
+        for x in 0..4 {
+            a[y][x] ^= b[y][x];
+        }
+    }
+}
+
+/// A small deterministic generator (splitmix64) for reproducible sampling.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A value in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Fills `bytes` with little-endian words from the generator.
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+/// A random 48-byte permutation input.
+pub fn random_state(rng: &mut Rng) -> [u8; 48] {
+    let mut state = [0u8; 48];
+    rng.fill(&mut state);
+    state
+}
+
+/// The numeric arguments of an analysis subcommand: `args[i]`, or `defaults[i]` when it is
+/// missing. Too many arguments or one that does not parse print the usage and exit.
+pub fn parse_args<const N: usize>(args: &[String], defaults: [usize; N]) -> [usize; N] {
+    if args.len() > N {
+        crate::usage();
+    }
+    std::array::from_fn(|i| {
+        args.get(i)
+            .map_or(Some(defaults[i]), |a| a.parse().ok())
+            .unwrap_or_else(|| crate::usage())
+    })
+}